@@ -0,0 +1,391 @@
+//! The original hand-written recursive-descent parser for Amarok.
+//!
+//! Superseded by the pest-based `amarok_parser` crate, but kept around for
+//! tooling that still depends on it. It understands a smaller statement set
+//! than the current language — notably `print` is a statement keyword here.
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(i64),
+    Identifier(String),
+    Print,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Equals,
+    Semicolon,
+    LeftParen,
+    RightParen,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub position: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.position)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    Print(Expression),
+    Assignment { name: String, value: Expression },
+    Expression(Expression),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(i64),
+    Variable(String),
+    Binary {
+        left: Box<Expression>,
+        operator: BinaryOp,
+        right: Box<Expression>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, ParseError> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+
+    while index < characters.len() {
+        let character = characters[index];
+        match character {
+            ' ' | '\t' | '\r' | '\n' => index += 1,
+            '0'..='9' => {
+                let start = index;
+                while index < characters.len() && characters[index].is_ascii_digit() {
+                    index += 1;
+                }
+                let text: String = characters[start..index].iter().collect();
+                let value = text
+                    .parse()
+                    .map_err(|_| ParseError::new(format!("invalid number: {}", text), start))?;
+                tokens.push(Token {
+                    kind: TokenKind::Number(value),
+                    position: start,
+                });
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let start = index;
+                while index < characters.len()
+                    && (characters[index].is_ascii_alphanumeric() || characters[index] == '_')
+                {
+                    index += 1;
+                }
+                let text: String = characters[start..index].iter().collect();
+                let kind = if text == "print" {
+                    TokenKind::Print
+                } else {
+                    TokenKind::Identifier(text)
+                };
+                tokens.push(Token {
+                    kind,
+                    position: start,
+                });
+            }
+            _ => {
+                let kind = match character {
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    '*' => TokenKind::Star,
+                    '/' => TokenKind::Slash,
+                    '=' => TokenKind::Equals,
+                    ';' => TokenKind::Semicolon,
+                    '(' => TokenKind::LeftParen,
+                    ')' => TokenKind::RightParen,
+                    other => {
+                        return Err(ParseError::new(
+                            format!("unexpected character: {}", other),
+                            index,
+                        ))
+                    }
+                };
+                tokens.push(Token {
+                    kind,
+                    position: index,
+                });
+                index += 1;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Self {
+            tokens,
+            position: 0,
+        }
+    }
+
+    /// Parse until the end of input, stopping at the first error.
+    pub fn parse_program(&mut self) -> Result<Vec<Statement>, ParseError> {
+        let mut statements = Vec::new();
+        while self.position < self.tokens.len() {
+            statements.push(self.parse_statement()?);
+        }
+        Ok(statements)
+    }
+
+    /// Parse until the end of input, recovering after each error so every
+    /// statement gets a chance. Returns the statements that parsed and all
+    /// errors encountered.
+    pub fn parse_program_with_recovery(&mut self) -> (Vec<Statement>, Vec<ParseError>) {
+        let mut statements = Vec::new();
+        let mut errors = Vec::new();
+        while self.position < self.tokens.len() {
+            match self.parse_statement() {
+                Ok(statement) => statements.push(statement),
+                Err(error) => {
+                    errors.push(error);
+                    self.synchronize();
+                }
+            }
+        }
+        (statements, errors)
+    }
+
+    /// Skip tokens until just past the next `;` or up to a token that can
+    /// start a statement, so parsing can resume on a fresh statement.
+    fn synchronize(&mut self) {
+        while let Some(kind) = self.peek_kind() {
+            match kind {
+                TokenKind::Semicolon => {
+                    self.advance();
+                    return;
+                }
+                TokenKind::Print => return,
+                _ => self.advance(),
+            }
+        }
+    }
+
+    fn parse_statement(&mut self) -> Result<Statement, ParseError> {
+        if self.check(&TokenKind::Print) {
+            self.advance();
+            let value = self.parse_expression()?;
+            self.expect(TokenKind::Semicolon)?;
+            return Ok(Statement::Print(value));
+        }
+
+        if let Some(TokenKind::Identifier(name)) = self.peek_kind() {
+            if self.peek_ahead_kind(1) == Some(&TokenKind::Equals) {
+                let name = name.clone();
+                self.advance();
+                self.advance();
+                let value = self.parse_expression()?;
+                self.expect(TokenKind::Semicolon)?;
+                return Ok(Statement::Assignment { name, value });
+            }
+        }
+
+        let expression = self.parse_expression()?;
+        self.expect(TokenKind::Semicolon)?;
+        Ok(Statement::Expression(expression))
+    }
+
+    fn parse_expression(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.parse_factor()?;
+        loop {
+            let operator = match self.peek_kind() {
+                Some(TokenKind::Plus) => BinaryOp::Add,
+                Some(TokenKind::Minus) => BinaryOp::Subtract,
+                _ => break,
+            };
+            self.advance();
+            let right = self.parse_factor()?;
+            left = Expression::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_factor(&mut self) -> Result<Expression, ParseError> {
+        let mut left = self.parse_primary()?;
+        loop {
+            let operator = match self.peek_kind() {
+                Some(TokenKind::Star) => BinaryOp::Multiply,
+                Some(TokenKind::Slash) => BinaryOp::Divide,
+                _ => break,
+            };
+            self.advance();
+            let right = self.parse_primary()?;
+            left = Expression::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<Expression, ParseError> {
+        match self.peek_kind().cloned() {
+            Some(TokenKind::Number(value)) => {
+                self.advance();
+                Ok(Expression::Number(value))
+            }
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                Ok(Expression::Variable(name))
+            }
+            Some(TokenKind::LeftParen) => {
+                self.advance();
+                let expression = self.parse_expression()?;
+                self.expect(TokenKind::RightParen)?;
+                Ok(expression)
+            }
+            Some(other) => Err(ParseError::new(
+                format!("expected an expression, found {:?}", other),
+                self.current_position(),
+            )),
+            None => Err(ParseError::new(
+                "unexpected end of input",
+                self.current_position(),
+            )),
+        }
+    }
+
+    fn peek_kind(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    fn peek_ahead_kind(&self, offset: usize) -> Option<&TokenKind> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|token| &token.kind)
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), ParseError> {
+        if self.check(&kind) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                format!("expected {:?}", kind),
+                self.current_position(),
+            ))
+        }
+    }
+
+    fn current_position(&self) -> usize {
+        self.tokens
+            .get(self.position)
+            .map(|token| token.position)
+            .unwrap_or_else(|| {
+                self.tokens
+                    .last()
+                    .map(|token| token.position + 1)
+                    .unwrap_or(0)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> Result<Vec<Statement>, ParseError> {
+        Parser::new(tokenize(source)?).parse_program()
+    }
+
+    #[test]
+    fn parse_print_statement() {
+        assert_eq!(
+            parse("print 1 + 2;").unwrap(),
+            vec![Statement::Print(Expression::Binary {
+                left: Box::new(Expression::Number(1)),
+                operator: BinaryOp::Add,
+                right: Box::new(Expression::Number(2)),
+            })]
+        );
+    }
+
+    #[test]
+    fn parse_assignment() {
+        assert_eq!(
+            parse("x = 4;").unwrap(),
+            vec![Statement::Assignment {
+                name: "x".to_string(),
+                value: Expression::Number(4),
+            }]
+        );
+    }
+
+    #[test]
+    fn stops_at_the_first_error() {
+        let error = parse("x = 1; y = ; z = 3;").unwrap_err();
+        assert_eq!(error.position, 11);
+    }
+
+    #[test]
+    fn recovery_keeps_statements_around_an_error() {
+        let tokens = tokenize("x = 1; y = ; z = 3;").unwrap();
+        let (statements, errors) = Parser::new(tokens).parse_program_with_recovery();
+        assert_eq!(
+            statements,
+            vec![
+                Statement::Assignment {
+                    name: "x".to_string(),
+                    value: Expression::Number(1),
+                },
+                Statement::Assignment {
+                    name: "z".to_string(),
+                    value: Expression::Number(3),
+                },
+            ]
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].position, 11);
+    }
+}
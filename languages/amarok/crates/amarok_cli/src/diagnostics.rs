@@ -0,0 +1,227 @@
+//! Human-readable rendering of parse and runtime errors, with a caret line
+//! pointing at the offending source.
+
+use amarok_interpreter::RuntimeError;
+use amarok_parser::ParseError;
+use amarok_syntax::{LineIndex, Span};
+
+pub fn render_parse_error(file: &str, source: &str, error: &ParseError) -> String {
+    render(file, source, &error.message, Some(error.span), Some(error.code()))
+}
+
+pub fn render_runtime_error(file: &str, source: &str, error: &RuntimeError) -> String {
+    render(file, source, &error.message, error.span, error.code())
+}
+
+pub fn render_runtime_warning(file: &str, source: &str, warning: &RuntimeError) -> String {
+    render_with_severity(file, source, &warning.message, warning.span, "warning", None)
+}
+
+fn render(
+    file: &str,
+    source: &str,
+    message: &str,
+    span: Option<Span>,
+    code: Option<&str>,
+) -> String {
+    render_with_severity(file, source, message, span, "error", code)
+}
+
+fn render_with_severity(
+    file: &str,
+    source: &str,
+    message: &str,
+    span: Option<Span>,
+    severity: &str,
+    code: Option<&str>,
+) -> String {
+    let mut rendered = match code {
+        Some(code) => format!("{}[{}]: {}\n", severity, code, message),
+        None => format!("{}: {}\n", severity, message),
+    };
+    let Some(span) = span else {
+        rendered.push_str(&format!(" --> {}\n", file));
+        return rendered;
+    };
+
+    let index = LineIndex::new(source);
+    let (line, column) = index.line_col(span.start);
+    let line_text = index.line_text(line).unwrap_or("");
+
+    let (end_line, end_column) = index.line_col(span.end);
+    let line_length = line_text.chars().count();
+    // Both ends clamp to the displayed line, so a span reaching past it never
+    // pushes the caret beyond the text it underlines.
+    let caret_start = (column - 1).min(line_length);
+    // A span crossing lines underlines to the end of its first line and says
+    // where the rest went.
+    let multi_line = end_line != line;
+    let caret_end = if multi_line {
+        line_length
+    } else {
+        (end_column - 1).min(line_length)
+    };
+    let caret_width = caret_end.saturating_sub(caret_start).max(1);
+
+    rendered.push_str(&format!(" --> {}:{}:{}\n", file, line, column));
+    rendered.push_str(&format!("{}\n", line_text));
+    rendered.push_str(&format!(
+        "{}{}{}\n",
+        " ".repeat(caret_start),
+        "^".repeat(caret_width),
+        if multi_line {
+            format!("... (continues through line {})", end_line)
+        } else {
+            String::new()
+        }
+    ));
+    rendered
+}
+
+pub fn render_parse_error_json(file: &str, source: &str, error: &ParseError) -> String {
+    render_json(file, source, &error.message, Some(error.span), Some(error.code()))
+}
+
+pub fn render_runtime_error_json(file: &str, source: &str, error: &RuntimeError) -> String {
+    render_json(file, source, &error.message, error.span, error.code())
+}
+
+pub fn render_runtime_warning_json(file: &str, source: &str, warning: &RuntimeError) -> String {
+    render_json_with_severity(file, source, &warning.message, warning.span, "warning", None)
+}
+
+/// One diagnostic as a single-line JSON object, for editors and LSP-style
+/// tooling. `start`/`end` are byte offsets; `line`/`column` are 1-based and
+/// `null` when the error has no span; `code` is the stable diagnostic code
+/// or `null` for uncoded diagnostics.
+fn render_json(
+    file: &str,
+    source: &str,
+    message: &str,
+    span: Option<Span>,
+    code: Option<&str>,
+) -> String {
+    render_json_with_severity(file, source, message, span, "error", code)
+}
+
+fn render_json_with_severity(
+    file: &str,
+    source: &str,
+    message: &str,
+    span: Option<Span>,
+    severity: &str,
+    code: Option<&str>,
+) -> String {
+    let (start, end, line, column) = match span {
+        Some(span) => {
+            let (line, column) = LineIndex::new(source).line_col(span.start);
+            (
+                span.start.to_string(),
+                span.end.to_string(),
+                line.to_string(),
+                column.to_string(),
+            )
+        }
+        None => (
+            "null".to_string(),
+            "null".to_string(),
+            "null".to_string(),
+            "null".to_string(),
+        ),
+    };
+    format!(
+        "{{\"file\":{},\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"message\":{},\"severity\":{},\"code\":{}}}\n",
+        escape_json(file),
+        start,
+        end,
+        line,
+        column,
+        escape_json(message),
+        escape_json(severity),
+        code.map(escape_json).unwrap_or_else(|| "null".to_string())
+    )
+}
+
+/// Quote and escape a string for JSON output.
+fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len() + 2);
+    escaped.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            control if (control as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", control as u32));
+            }
+            other => escaped.push(other),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_caret_under_the_span() {
+        let source = "x = 1;\ny = missing;\n";
+        let error = RuntimeError::new("Undefined variable: missing", Span::new(11, 18));
+        let rendered = render_runtime_error("test.amarok", source, &error);
+        assert!(rendered.contains("test.amarok:2:5"));
+        assert!(rendered.contains("y = missing;"));
+        assert!(rendered.contains("    ^^^^^^^"));
+    }
+
+    #[test]
+    fn a_multi_line_span_underlines_only_the_first_line() {
+        let source = "x = [1,\n2];\n";
+        let error = RuntimeError::new("Unsupported operation: demo", Span::new(4, 10));
+        let rendered = render_runtime_error("test.amarok", source, &error);
+        assert!(rendered.contains("x = [1,\n"));
+        assert!(rendered.contains("    ^^^... (continues through line 2)\n"));
+        // The caret never reaches past the displayed line.
+        assert!(!rendered.contains("^^^^"));
+    }
+
+    #[test]
+    fn json_rendering_carries_span_and_position() {
+        let source = "x = 1;\ny = missing;\n";
+        let error = RuntimeError::new("Undefined variable: missing", Span::new(11, 18));
+        let json = render_runtime_error_json("test.amarok", source, &error);
+        assert_eq!(
+            json,
+            "{\"file\":\"test.amarok\",\"start\":11,\"end\":18,\"line\":2,\"column\":5,\
+             \"message\":\"Undefined variable: missing\",\"severity\":\"error\",\
+             \"code\":\"E0001\"}\n"
+        );
+    }
+
+    #[test]
+    fn division_by_zero_renders_with_its_code() {
+        let source = "x = 1 / 0;\n";
+        let error = RuntimeError::new("division by zero", Span::new(4, 9));
+        let rendered = render_runtime_error("test.amarok", source, &error);
+        assert!(rendered.starts_with("error[E0003]: division by zero\n"));
+    }
+
+    #[test]
+    fn uncoded_diagnostics_render_without_brackets() {
+        let source = "x = 1;\n";
+        let warning = RuntimeError::new("something odd", Span::new(0, 6));
+        let rendered = render_runtime_warning("test.amarok", source, &warning);
+        assert!(rendered.starts_with("warning: something odd\n"));
+        let json = render_runtime_warning_json("test.amarok", source, &warning);
+        assert!(json.contains("\"code\":null"));
+    }
+
+    #[test]
+    fn json_rendering_escapes_quotes_and_newlines() {
+        assert_eq!(escape_json("say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+    }
+
+}
@@ -0,0 +1,94 @@
+//! The `// expect:` golden-test harness: a script annotates its expected
+//! output in comments and `amarok test` compares the program's printed
+//! lines against them, in order. A file without any `// expect:` comment
+//! is not a golden test and passes on assertions alone.
+
+/// The expectation after each `// expect:` marker, in source order. The
+/// text is trimmed, so `print(x); // expect: 42` expects the line `42`;
+/// a comment on its own line works the same way.
+pub fn expectations(source: &str) -> Vec<String> {
+    source
+        .lines()
+        .filter_map(|line| line.split_once("// expect:"))
+        .map(|(_, expected)| expected.trim().to_string())
+        .collect()
+}
+
+/// Compare `output` against the `// expect:` comments in `source`, line by
+/// line. Each mismatch becomes one human-readable message; an empty vector
+/// means the golden test passes.
+pub fn check_expectations(source: &str, output: &[String]) -> Vec<String> {
+    let expected = expectations(source);
+    if expected.is_empty() {
+        return Vec::new();
+    }
+    let mut failures = Vec::new();
+    for index in 0..expected.len().max(output.len()) {
+        match (expected.get(index), output.get(index)) {
+            (Some(want), Some(got)) if want == got => {}
+            (Some(want), Some(got)) => failures.push(format!(
+                "output line {}: expected `{}`, got `{}`",
+                index + 1,
+                want,
+                got
+            )),
+            (Some(want), None) => failures.push(format!(
+                "output line {}: expected `{}`, got nothing",
+                index + 1,
+                want
+            )),
+            (None, Some(got)) => {
+                failures.push(format!("output line {}: unexpected `{}`", index + 1, got))
+            }
+            (None, None) => {}
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_interpreter::Interpreter;
+    use amarok_parser::parse_program;
+
+    #[test]
+    fn expectations_come_from_trailing_and_standalone_comments() {
+        let source = "print(1); // expect: 1\n// expect: two words\nprint(3);\n";
+        assert_eq!(expectations(source), vec!["1", "two words"]);
+    }
+
+    #[test]
+    fn each_kind_of_mismatch_gets_its_own_message() {
+        let source = "// expect: 1\n// expect: 2\n";
+        let output = vec!["1".to_string(), "3".to_string(), "4".to_string()];
+        assert_eq!(
+            check_expectations(source, &output),
+            vec![
+                "output line 2: expected `2`, got `3`",
+                "output line 3: unexpected `4`",
+            ]
+        );
+        assert_eq!(
+            check_expectations(source, &["1".to_string()]),
+            vec!["output line 2: expected `2`, got nothing"]
+        );
+    }
+
+    #[test]
+    fn a_file_without_expect_comments_is_not_a_golden_test() {
+        assert!(check_expectations("print(1);\n", &["1".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn the_sample_file_passes_its_own_expectations() {
+        let source = include_str!("../../../examples/expect.amarok");
+        let program = parse_program(source).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(
+            check_expectations(source, interpreter.output_lines()),
+            Vec::<String>::new()
+        );
+    }
+}
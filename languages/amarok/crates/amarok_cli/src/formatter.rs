@@ -0,0 +1,477 @@
+//! The `fmt` subcommand's pretty-printer: renders a parsed program back as
+//! canonical source — four-space indent, one space around binary operators,
+//! opening brace on the statement's line, no parentheses around conditions.
+//!
+//! The printer works from the AST, so only what the AST keeps survives:
+//! `///` doc comments ride on function definitions and are reprinted, while
+//! plain `//` comments are skipped by the grammar and dropped. Compound
+//! assignment keeps its original spelling through the `Desugared` wrapper.
+
+use amarok_syntax::ast::{Expression, Program, Statement};
+use amarok_syntax::Spanned;
+
+pub fn format_program(program: &Program) -> String {
+    let mut out = String::new();
+    for statement in &program.statements {
+        write_statement(&mut out, statement, 0);
+    }
+    out
+}
+
+fn indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("    ");
+    }
+}
+
+fn write_statement(out: &mut String, statement: &Spanned<Statement>, depth: usize) {
+    match &statement.value {
+        Statement::Assignment { name, value } => {
+            indent(out, depth);
+            out.push_str(name);
+            out.push_str(" = ");
+            write_expression(out, &value.value, depth);
+            out.push_str(";\n");
+        }
+        // The original spelling (`x += 1`) is the canonical form.
+        Statement::Desugared { original, .. } => {
+            indent(out, depth);
+            out.push_str(original);
+            out.push_str(";\n");
+        }
+        Statement::Let {
+            name,
+            type_annotation,
+            value,
+        } => {
+            indent(out, depth);
+            out.push_str("let ");
+            out.push_str(name);
+            if let Some(annotation) = type_annotation {
+                out.push_str(": ");
+                out.push_str(annotation.name());
+            }
+            out.push_str(" = ");
+            write_expression(out, &value.value, depth);
+            out.push_str(";\n");
+        }
+        Statement::IndexAssignment {
+            target,
+            index,
+            value,
+        } => {
+            indent(out, depth);
+            write_expression(out, &target.value, depth);
+            out.push('[');
+            write_expression(out, &index.value, depth);
+            out.push_str("] = ");
+            write_expression(out, &value.value, depth);
+            out.push_str(";\n");
+        }
+        Statement::FunctionDefinition {
+            name,
+            parameters,
+            body,
+            doc,
+        } => {
+            if let Some(doc) = doc {
+                // Doc lines are stored trimmed, so the canonical form puts
+                // one space after the markers.
+                for line in doc.lines() {
+                    indent(out, depth);
+                    out.push_str("/// ");
+                    out.push_str(line);
+                    out.push('\n');
+                }
+            }
+            indent(out, depth);
+            out.push_str("def ");
+            out.push_str(name);
+            out.push('(');
+            out.push_str(&parameters.join(", "));
+            out.push(')');
+            write_block(out, body, depth);
+            out.push('\n');
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            indent(out, depth);
+            write_if(out, condition, then_branch, else_branch.as_deref(), depth);
+            out.push('\n');
+        }
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+        } => {
+            indent(out, depth);
+            out.push_str("while ");
+            write_expression(out, &condition.value, depth);
+            write_block(out, body, depth);
+            if let Some(else_branch) = else_branch {
+                out.push_str(" else");
+                write_block(out, else_branch, depth);
+            }
+            out.push('\n');
+        }
+        Statement::Match { scrutinee, arms } => {
+            indent(out, depth);
+            out.push_str("match ");
+            write_expression(out, &scrutinee.value, depth);
+            out.push_str(" {");
+            if !arms.is_empty() {
+                out.push('\n');
+                for arm in arms {
+                    indent(out, depth + 1);
+                    match &arm.pattern {
+                        Some(pattern) => write_expression(out, &pattern.value, depth + 1),
+                        None => out.push('_'),
+                    }
+                    out.push_str(" =>");
+                    write_block(out, &arm.body, depth + 1);
+                    out.push('\n');
+                }
+                indent(out, depth);
+            }
+            out.push_str("}\n");
+        }
+        Statement::ForIn {
+            variable,
+            iterable,
+            body,
+        } => {
+            indent(out, depth);
+            out.push_str("for ");
+            out.push_str(variable);
+            out.push_str(" in ");
+            write_expression(out, &iterable.value, depth);
+            write_block(out, body, depth);
+            out.push('\n');
+        }
+        Statement::Return { value } => {
+            indent(out, depth);
+            out.push_str("return");
+            if let Some(value) = value {
+                out.push(' ');
+                write_expression(out, &value.value, depth);
+            }
+            out.push_str(";\n");
+        }
+        Statement::Break => {
+            indent(out, depth);
+            out.push_str("break;\n");
+        }
+        Statement::Continue => {
+            indent(out, depth);
+            out.push_str("continue;\n");
+        }
+        Statement::Block(body) => {
+            indent(out, depth);
+            out.push('{');
+            write_block_body(out, body, depth);
+            out.push_str("}\n");
+        }
+        Statement::Expression(expression) => {
+            indent(out, depth);
+            write_expression(out, &expression.value, depth);
+            out.push_str(";\n");
+        }
+    }
+}
+
+/// Writes `if condition { ... }` plus any else chain, without the leading
+/// indent or trailing newline — the caller owns those so `else if` can
+/// continue on the closing brace's line.
+fn write_if(
+    out: &mut String,
+    condition: &Spanned<Expression>,
+    then_branch: &[Spanned<Statement>],
+    else_branch: Option<&[Spanned<Statement>]>,
+    depth: usize,
+) {
+    out.push_str("if ");
+    write_expression(out, &condition.value, depth);
+    write_block(out, then_branch, depth);
+    if let Some(else_branch) = else_branch {
+        out.push_str(" else");
+        // An `else if` chain is a single nested `If`; keep it on one line
+        // rather than indenting a block around it.
+        if let [only] = else_branch {
+            if let Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } = &only.value
+            {
+                out.push(' ');
+                write_if(out, condition, then_branch, else_branch.as_deref(), depth);
+                return;
+            }
+        }
+        write_block(out, else_branch, depth);
+    }
+}
+
+/// ` { ... }` after a statement header; the closing brace lands on its own
+/// line at `depth`, with no trailing newline.
+fn write_block(out: &mut String, body: &[Spanned<Statement>], depth: usize) {
+    out.push_str(" {");
+    write_block_body(out, body, depth);
+    out.push('}');
+}
+
+fn write_block_body(out: &mut String, body: &[Spanned<Statement>], depth: usize) {
+    if body.is_empty() {
+        return;
+    }
+    out.push('\n');
+    for statement in body {
+        write_statement(out, statement, depth + 1);
+    }
+    indent(out, depth);
+}
+
+/// Binding strength of a binary operator, used to decide which children need
+/// parentheses. Mirrors the grammar's precedence ladder; higher binds tighter.
+fn precedence(operator: amarok_syntax::ast::BinaryOperator) -> u8 {
+    use amarok_syntax::ast::BinaryOperator::*;
+    match operator {
+        Or => 1,
+        And => 2,
+        Equal | NotEqual => 3,
+        Less | LessEqual | Greater | GreaterEqual | In => 4,
+        Add | Subtract => 5,
+        Multiply | Divide | FloorDivide | Modulo => 6,
+    }
+}
+
+fn write_expression(out: &mut String, expression: &Expression, depth: usize) {
+    match expression {
+        Expression::Null => out.push_str("null"),
+        Expression::Integer(value) => out.push_str(&value.to_string()),
+        // `{:?}` keeps a `.0` or exponent, so the literal reparses as a float.
+        Expression::Float(value) => out.push_str(&format!("{:?}", value)),
+        Expression::Boolean(value) => out.push_str(if *value { "true" } else { "false" }),
+        Expression::Char(value) => {
+            out.push('\'');
+            push_escaped(out, *value, '\'');
+            out.push('\'');
+        }
+        Expression::String(value) => {
+            out.push('"');
+            for character in value.chars() {
+                push_escaped(out, character, '"');
+            }
+            out.push('"');
+        }
+        Expression::Variable(name) => out.push_str(name),
+        Expression::Array(elements) => {
+            out.push('[');
+            write_separated(out, elements, depth);
+            out.push(']');
+        }
+        Expression::Tuple(elements) => {
+            out.push('(');
+            write_separated(out, elements, depth);
+            if elements.len() == 1 {
+                out.push(',');
+            }
+            out.push(')');
+        }
+        Expression::Map(entries) => {
+            out.push('{');
+            for (position, (key, value)) in entries.iter().enumerate() {
+                if position > 0 {
+                    out.push_str(", ");
+                }
+                if is_bare_map_key(key) {
+                    out.push_str(key);
+                } else {
+                    out.push('"');
+                    for character in key.chars() {
+                        push_escaped(out, character, '"');
+                    }
+                    out.push('"');
+                }
+                out.push_str(": ");
+                write_expression(out, &value.value, depth);
+            }
+            out.push('}');
+        }
+        Expression::Index { target, index } => {
+            write_postfix_operand(out, &target.value, depth);
+            out.push('[');
+            write_expression(out, &index.value, depth);
+            out.push(']');
+        }
+        Expression::Unary { operator, operand } => {
+            out.push_str(operator.symbol());
+            write_postfix_operand(out, &operand.value, depth);
+        }
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            let own = precedence(*operator);
+            write_child(out, &left.value, own, depth);
+            out.push(' ');
+            out.push_str(operator.symbol());
+            out.push(' ');
+            // Operators are left-associative, so an equal-precedence right
+            // child keeps its parentheses: `a - (b - c)`.
+            write_child(out, &right.value, own + 1, depth);
+        }
+        Expression::FunctionCall { name, arguments } => {
+            out.push_str(name);
+            out.push('(');
+            write_separated(out, arguments, depth);
+            out.push(')');
+        }
+        Expression::MethodCall {
+            receiver,
+            name,
+            arguments,
+        } => {
+            write_postfix_operand(out, &receiver.value, depth);
+            out.push('.');
+            out.push_str(name);
+            out.push('(');
+            write_separated(out, arguments, depth);
+            out.push(')');
+        }
+        Expression::Lambda { parameters, body } => {
+            out.push_str("fn(");
+            out.push_str(&parameters.join(", "));
+            out.push(')');
+            write_block(out, body, depth);
+        }
+    }
+}
+
+/// Writes a binary operand, parenthesized when it binds less tightly than
+/// `minimum` requires.
+fn write_child(out: &mut String, child: &Expression, minimum: u8, depth: usize) {
+    let needs_parens = match child {
+        Expression::Binary { operator, .. } => precedence(*operator) < minimum,
+        _ => false,
+    };
+    if needs_parens {
+        out.push('(');
+        write_expression(out, child, depth);
+        out.push(')');
+    } else {
+        write_expression(out, child, depth);
+    }
+}
+
+/// Writes an index target, method receiver, or unary operand — positions that
+/// bind tighter than any operator, so operator children need parentheses.
+fn write_postfix_operand(out: &mut String, operand: &Expression, depth: usize) {
+    if matches!(
+        operand,
+        Expression::Binary { .. } | Expression::Unary { .. }
+    ) {
+        out.push('(');
+        write_expression(out, operand, depth);
+        out.push(')');
+    } else {
+        write_expression(out, operand, depth);
+    }
+}
+
+fn write_separated(out: &mut String, expressions: &[Spanned<Expression>], depth: usize) {
+    for (position, expression) in expressions.iter().enumerate() {
+        if position > 0 {
+            out.push_str(", ");
+        }
+        write_expression(out, &expression.value, depth);
+    }
+}
+
+fn push_escaped(out: &mut String, character: char, quote: char) {
+    match character {
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\t' => out.push_str("\\t"),
+        '\r' => out.push_str("\\r"),
+        c if c == quote => {
+            out.push('\\');
+            out.push(c);
+        }
+        c => out.push(c),
+    }
+}
+
+/// A map key prints bare only when it would parse back as an identifier —
+/// checked by parsing it, so the keyword list lives in one place.
+fn is_bare_map_key(key: &str) -> bool {
+    matches!(
+        amarok_parser::parse_expression(key),
+        Ok(parsed) if matches!(&parsed.value, Expression::Variable(name) if name == key)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_parser::parse_program;
+
+    fn format(source: &str) -> String {
+        format_program(&parse_program(source).unwrap())
+    }
+
+    #[test]
+    fn formatting_normalizes_spacing_and_indentation() {
+        let formatted = format("if(x>0){y=1+2*3;}else{y=0;}");
+        assert_eq!(
+            formatted,
+            "if x > 0 {\n    y = 1 + 2 * 3;\n} else {\n    y = 0;\n}\n"
+        );
+    }
+
+    #[test]
+    fn formatting_is_idempotent() {
+        let source = "\
+///Adds one.
+def bump(x) {
+    x += 1;
+    return x;
+}
+
+let m = {a: 1, \"two words\": 2};
+for item in [1, 2, (3,)] {
+    if item in m || !done {
+        print(m[\"two words\"] - (item - 1));
+    } else if item {
+        break;
+    }
+}
+while x < 10 {
+    x = (x + 1) * 2;
+} else {
+    print(fn(a) { return -a; });
+}
+";
+        let once = format(source);
+        let twice = format_program(&parse_program(&once).unwrap());
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn needed_parentheses_survive_and_redundant_ones_go() {
+        assert_eq!(format("x = ((a + b)) * (c - d);"), "x = (a + b) * (c - d);\n");
+        assert_eq!(format("x = a - (b - c);"), "x = a - (b - c);\n");
+        assert_eq!(format("x = (a - b) - c;"), "x = a - b - c;\n");
+        assert_eq!(format("x = (-a).len();"), "x = (-a).len();\n");
+    }
+
+    #[test]
+    fn a_quoted_map_key_stays_quoted_only_when_it_must() {
+        assert_eq!(format("m = {\"plain\": 1};"), "m = {plain: 1};\n");
+        assert_eq!(format("m = {\"if\": 1};"), "m = {\"if\": 1};\n");
+    }
+}
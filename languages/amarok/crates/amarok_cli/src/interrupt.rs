@@ -0,0 +1,34 @@
+//! Routes Ctrl-C to the interpreter's interrupt flag so a runaway loop stops
+//! with an "interrupted" error instead of the whole process dying.
+//!
+//! Platform support: on Unix the handler is registered through the C
+//! runtime's `signal`, which every binary already links. Elsewhere `install`
+//! is a no-op and Ctrl-C keeps its default process-killing behavior.
+
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, OnceLock};
+
+static FLAG: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+#[cfg(unix)]
+extern "C" fn handle_sigint(_signal: i32) {
+    // Only the lock-free store is signal-safe; everything else happens on
+    // the interpreter thread when it polls the flag.
+    if let Some(flag) = FLAG.get() {
+        flag.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+}
+
+/// Make Ctrl-C set `flag`. The first installed flag serves the whole
+/// process; later calls are ignored.
+pub fn install(flag: Arc<AtomicBool>) {
+    let _ = FLAG.set(flag);
+    #[cfg(unix)]
+    unsafe {
+        extern "C" {
+            fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        }
+        // SIGINT is 2 on every Unix.
+        signal(2, handle_sigint);
+    }
+}
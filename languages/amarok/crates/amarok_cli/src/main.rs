@@ -0,0 +1,397 @@
+//! The `amarok` command: run scripts or start an interactive session.
+
+mod diagnostics;
+mod expect;
+mod formatter;
+mod interrupt;
+mod span_dump;
+
+use std::io::{self, BufRead, Write};
+use std::{env, fs, process};
+
+use amarok_interpreter::Interpreter;
+
+/// How diagnostics are written to stderr.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorFormat {
+    /// Caret rendering for humans.
+    Human,
+    /// One JSON object per diagnostic, for editors.
+    Json,
+}
+
+/// How many diagnostics are printed before the rest collapse into a single
+/// "... and M more" line. Badly broken files can produce hundreds.
+const DEFAULT_MAX_ERRORS: usize = 20;
+
+fn main() {
+    let mut arguments: Vec<String> = env::args().skip(1).collect();
+    let mut error_format = ErrorFormat::Human;
+    let mut max_errors = DEFAULT_MAX_ERRORS;
+    let mut verbose = false;
+    let mut dump_spans = false;
+    let mut strict = false;
+    let mut write = false;
+    let mut max_heap_bytes = None;
+    let mut bad_flag = false;
+    arguments.retain(|argument| match argument.as_str() {
+        "--verbose" => {
+            verbose = true;
+            false
+        }
+        // `-Werror` is the compiler-familiar spelling of the same switch.
+        "--strict" | "-Werror" => {
+            strict = true;
+            false
+        }
+        "--dump-spans" => {
+            dump_spans = true;
+            false
+        }
+        "--write" => {
+            write = true;
+            false
+        }
+        "--error-format=human" => {
+            error_format = ErrorFormat::Human;
+            false
+        }
+        "--error-format=json" => {
+            error_format = ErrorFormat::Json;
+            false
+        }
+        argument if argument.starts_with("--max-errors=") => {
+            match argument["--max-errors=".len()..].parse() {
+                Ok(limit) => max_errors = limit,
+                Err(_) => bad_flag = true,
+            }
+            false
+        }
+        argument if argument.starts_with("--max-heap-bytes=") => {
+            match argument["--max-heap-bytes=".len()..].parse() {
+                Ok(limit) => max_heap_bytes = Some(limit),
+                Err(_) => bad_flag = true,
+            }
+            false
+        }
+        _ => true,
+    });
+    if bad_flag {
+        usage();
+    }
+    match arguments.first().map(String::as_str) {
+        Some("run") => match arguments.get(1) {
+            Some(path) => run_file(path, error_format, max_errors, verbose, strict, max_heap_bytes),
+            None => usage(),
+        },
+        Some("check") => match arguments.get(1) {
+            Some(path) => check_file(path, error_format, max_errors),
+            None => usage(),
+        },
+        Some("ast") => match arguments.get(1) {
+            Some(path) => dump_ast(path, error_format, dump_spans),
+            None => usage(),
+        },
+        Some("test") => match arguments.get(1) {
+            Some(path) => run_tests(path, error_format),
+            None => usage(),
+        },
+        Some("fmt") => match arguments.get(1) {
+            Some(path) => format_file(path, error_format, write),
+            None => usage(),
+        },
+        Some("repl") => repl(),
+        _ => usage(),
+    }
+}
+
+fn usage() -> ! {
+    eprintln!(
+        "usage: amarok [--error-format=human|json] [--max-errors=N] [--max-heap-bytes=N] \
+         [--verbose] [--dump-spans] [--strict] [--write] \
+         <run FILE | check FILE | test FILE | ast FILE | fmt FILE | repl>"
+    );
+    process::exit(2);
+}
+
+/// Reprint FILE in canonical style, to stdout by default or back into the
+/// file with `--write`. A file that does not parse is left untouched.
+fn format_file(path: &str, error_format: ErrorFormat, write: bool) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+    let program = match amarok_parser::parse_program(&source) {
+        Ok(program) => program,
+        Err(error) => {
+            let rendered = match error_format {
+                ErrorFormat::Human => diagnostics::render_parse_error(path, &source, &error),
+                ErrorFormat::Json => diagnostics::render_parse_error_json(path, &source, &error),
+            };
+            eprint!("{}", rendered);
+            process::exit(1);
+        }
+    };
+    let formatted = formatter::format_program(&program);
+    if !write {
+        print!("{}", formatted);
+    } else if formatted != source {
+        if let Err(error) = fs::write(path, &formatted) {
+            eprintln!("error: cannot write {}: {}", path, error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Parse FILE with recovery and report every syntax error, without running
+/// anything. At most `max_errors` diagnostics are printed; the rest collapse
+/// into a count. Exits non-zero when the file has any errors.
+fn check_file(path: &str, error_format: ErrorFormat, max_errors: usize) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+    let (_, errors) = amarok_parser::parse_program_with_recovery(&source);
+    if errors.is_empty() {
+        println!("check {}: ok", path);
+        return;
+    }
+    for error in errors.iter().take(max_errors) {
+        let rendered = match error_format {
+            ErrorFormat::Human => diagnostics::render_parse_error(path, &source, error),
+            ErrorFormat::Json => diagnostics::render_parse_error_json(path, &source, error),
+        };
+        eprint!("{}", rendered);
+    }
+    if errors.len() > max_errors {
+        eprintln!("... and {} more errors", errors.len() - max_errors);
+    }
+    process::exit(1);
+}
+
+/// Print the program as S-expressions, one statement per line; with
+/// `--dump-spans`, print each node with the source text its span covers
+/// instead.
+fn dump_ast(path: &str, error_format: ErrorFormat, dump_spans: bool) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+    match amarok_parser::parse_program(&source) {
+        Ok(program) if dump_spans => print!("{}", span_dump::dump_spans(&source, &program)),
+        Ok(program) => println!("{}", program.to_sexpr()),
+        Err(error) => {
+            let rendered = match error_format {
+                ErrorFormat::Human => diagnostics::render_parse_error(path, &source, &error),
+                ErrorFormat::Json => diagnostics::render_parse_error_json(path, &source, &error),
+            };
+            eprint!("{}", rendered);
+            process::exit(1);
+        }
+    }
+}
+
+/// With `strict`, warnings are still rendered as warnings but any warning
+/// makes the run exit non-zero, so CI can refuse scripts that only warn.
+/// `--max-heap-bytes=N` gives untrusted scripts a soft allocation budget.
+fn run_file(
+    path: &str,
+    error_format: ErrorFormat,
+    max_errors: usize,
+    verbose: bool,
+    strict: bool,
+    max_heap_bytes: Option<usize>,
+) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    let program = match amarok_parser::parse_program(&source) {
+        Ok(program) => program,
+        Err(error) => {
+            let rendered = match error_format {
+                ErrorFormat::Human => diagnostics::render_parse_error(path, &source, &error),
+                ErrorFormat::Json => diagnostics::render_parse_error_json(path, &source, &error),
+            };
+            eprint!("{}", rendered);
+            process::exit(1);
+        }
+    };
+
+    let mut interpreter = Interpreter::new();
+    interpreter.set_trace(verbose);
+    interpreter.set_max_heap_bytes(max_heap_bytes);
+    interrupt::install(interpreter.interrupt_flag());
+    let result = interpreter.run_program(&program);
+    for line in interpreter.output_lines() {
+        println!("{}", line);
+    }
+    for line in interpreter.trace_lines() {
+        eprintln!("trace: {}", line);
+    }
+    if verbose {
+        eprintln!("allocated: about {} bytes", interpreter.allocated_bytes());
+    }
+    for warning in interpreter.warnings().iter().take(max_errors) {
+        let rendered = match error_format {
+            ErrorFormat::Human => diagnostics::render_runtime_warning(path, &source, warning),
+            ErrorFormat::Json => diagnostics::render_runtime_warning_json(path, &source, warning),
+        };
+        eprint!("{}", rendered);
+    }
+    if interpreter.warnings().len() > max_errors {
+        eprintln!(
+            "... and {} more warnings",
+            interpreter.warnings().len() - max_errors
+        );
+    }
+    if let Err(error) = result {
+        let rendered = match error_format {
+            ErrorFormat::Human => diagnostics::render_runtime_error(path, &source, &error),
+            ErrorFormat::Json => diagnostics::render_runtime_error_json(path, &source, &error),
+        };
+        eprint!("{}", rendered);
+        process::exit(1);
+    }
+    if strict && !interpreter.warnings().is_empty() {
+        eprintln!(
+            "error: {} warning(s) treated as errors (--strict)",
+            interpreter.warnings().len()
+        );
+        process::exit(1);
+    }
+}
+
+/// Run FILE as a test: a failed `assert`/`assert_eq` is a test failure,
+/// reported with the span of the assertion, and any `// expect:` comments
+/// are checked against the printed output line by line. Exits non-zero on
+/// any failure.
+///
+/// The interpreter stops at the first runtime error, so one failure is
+/// reported per run; fix it and rerun for the next.
+fn run_tests(path: &str, error_format: ErrorFormat) {
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("error: cannot read {}: {}", path, error);
+            process::exit(1);
+        }
+    };
+
+    let program = match amarok_parser::parse_program(&source) {
+        Ok(program) => program,
+        Err(error) => {
+            let rendered = match error_format {
+                ErrorFormat::Human => diagnostics::render_parse_error(path, &source, &error),
+                ErrorFormat::Json => diagnostics::render_parse_error_json(path, &source, &error),
+            };
+            eprint!("{}", rendered);
+            process::exit(1);
+        }
+    };
+
+    let mut interpreter = Interpreter::new();
+    let result = interpreter.run_program(&program);
+    for line in interpreter.output_lines() {
+        println!("{}", line);
+    }
+    match result {
+        Ok(()) => {
+            // A clean run can still fail its `// expect:` comments.
+            let failures = expect::check_expectations(&source, interpreter.output_lines());
+            if failures.is_empty() {
+                println!("test {}: ok", path);
+            } else {
+                for failure in &failures {
+                    eprintln!("{}: {}", path, failure);
+                }
+                eprintln!("test {}: FAILED", path);
+                process::exit(1);
+            }
+        }
+        Err(error) => {
+            let failed_assertion = error.message.starts_with("assertion failed");
+            let rendered = match error_format {
+                ErrorFormat::Human => diagnostics::render_runtime_error(path, &source, &error),
+                ErrorFormat::Json => diagnostics::render_runtime_error_json(path, &source, &error),
+            };
+            eprint!("{}", rendered);
+            if failed_assertion {
+                eprintln!("test {}: FAILED", path);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+fn repl() {
+    let stdin = io::stdin();
+    let mut interpreter = Interpreter::new();
+    interrupt::install(interpreter.interrupt_flag());
+    let mut printed = 0;
+
+    loop {
+        print!(">> ");
+        io::stdout().flush().expect("stdout should be writable");
+
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("error: {}", error);
+                break;
+            }
+        }
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line == "exit" {
+            break;
+        }
+
+        // A trailing expression may omit its `;` at the prompt, so a failed
+        // parse is retried with one appended: `a = 1; a + 1` evaluates both
+        // statements and echoes `2`.
+        let program = match amarok_parser::parse_program(line) {
+            Ok(program) => program,
+            Err(original) => match amarok_parser::parse_program(&format!("{};", line)) {
+                Ok(program) => program,
+                Err(_) => {
+                    eprintln!("parse error: {}", original.message);
+                    continue;
+                }
+            },
+        };
+
+        match interpreter.eval_program(&program) {
+            Ok(value) => {
+                for line in &interpreter.output_lines()[printed..] {
+                    println!("{}", line);
+                }
+                printed = interpreter.output_lines().len();
+                if let Some(value) = value {
+                    println!("{}", value.repr());
+                }
+            }
+            Err(error) => {
+                printed = interpreter.output_lines().len();
+                eprintln!("runtime error: {}", error.message);
+            }
+        }
+    }
+}
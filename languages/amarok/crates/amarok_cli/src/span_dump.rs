@@ -0,0 +1,229 @@
+//! The `--dump-spans` rendering: every AST node with the source text its
+//! span covers, so a span that is too wide or too narrow is visible at a
+//! glance.
+
+use amarok_syntax::ast::{Expression, Program, Statement};
+use amarok_syntax::{Span, Spanned};
+
+pub fn dump_spans(source: &str, program: &Program) -> String {
+    let mut out = String::new();
+    for statement in &program.statements {
+        write_statement(&mut out, source, statement, 0);
+    }
+    out
+}
+
+fn slice(source: &str, span: Span) -> &str {
+    source.get(span.start..span.end).unwrap_or("<out of range>")
+}
+
+fn push_line(out: &mut String, source: &str, label: &str, span: Span, depth: usize) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(label);
+    out.push_str("[«");
+    out.push_str(slice(source, span));
+    out.push_str("»]\n");
+}
+
+fn write_statement(out: &mut String, source: &str, statement: &Spanned<Statement>, depth: usize) {
+    match &statement.value {
+        Statement::Assignment { value, .. } => {
+            push_line(out, source, "Assignment", statement.span, depth);
+            write_expression(out, source, value, depth + 1);
+        }
+        Statement::Desugared {
+            statement: synthesized,
+            ..
+        } => {
+            push_line(out, source, "Desugared", statement.span, depth);
+            write_statement(out, source, synthesized, depth + 1);
+        }
+        Statement::Let { value, .. } => {
+            push_line(out, source, "Let", statement.span, depth);
+            write_expression(out, source, value, depth + 1);
+        }
+        Statement::IndexAssignment {
+            target,
+            index,
+            value,
+        } => {
+            push_line(out, source, "IndexAssignment", statement.span, depth);
+            write_expression(out, source, target, depth + 1);
+            write_expression(out, source, index, depth + 1);
+            write_expression(out, source, value, depth + 1);
+        }
+        Statement::FunctionDefinition { body, .. } => {
+            push_line(out, source, "FunctionDefinition", statement.span, depth);
+            for statement in body {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            push_line(out, source, "If", statement.span, depth);
+            write_expression(out, source, condition, depth + 1);
+            for statement in then_branch {
+                write_statement(out, source, statement, depth + 1);
+            }
+            for statement in else_branch.iter().flatten() {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+        } => {
+            push_line(out, source, "While", statement.span, depth);
+            write_expression(out, source, condition, depth + 1);
+            for statement in body {
+                write_statement(out, source, statement, depth + 1);
+            }
+            for statement in else_branch.iter().flatten() {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+        Statement::Match { scrutinee, arms } => {
+            push_line(out, source, "Match", statement.span, depth);
+            write_expression(out, source, scrutinee, depth + 1);
+            for arm in arms {
+                if let Some(pattern) = &arm.pattern {
+                    write_expression(out, source, pattern, depth + 1);
+                }
+                for statement in &arm.body {
+                    write_statement(out, source, statement, depth + 1);
+                }
+            }
+        }
+        Statement::ForIn {
+            iterable, body, ..
+        } => {
+            push_line(out, source, "ForIn", statement.span, depth);
+            write_expression(out, source, iterable, depth + 1);
+            for statement in body {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+        Statement::Return { value } => {
+            push_line(out, source, "Return", statement.span, depth);
+            if let Some(value) = value {
+                write_expression(out, source, value, depth + 1);
+            }
+        }
+        Statement::Break => push_line(out, source, "Break", statement.span, depth),
+        Statement::Continue => push_line(out, source, "Continue", statement.span, depth),
+        Statement::Block(statements) => {
+            push_line(out, source, "Block", statement.span, depth);
+            for statement in statements {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+        Statement::Expression(expression) => {
+            push_line(out, source, "ExpressionStatement", statement.span, depth);
+            write_expression(out, source, expression, depth + 1);
+        }
+    }
+}
+
+fn write_expression(
+    out: &mut String,
+    source: &str,
+    expression: &Spanned<Expression>,
+    depth: usize,
+) {
+    let span = expression.span;
+    match &expression.value {
+        Expression::Null => push_line(out, source, "Null", span, depth),
+        Expression::Integer(_) => push_line(out, source, "Integer", span, depth),
+        Expression::Float(_) => push_line(out, source, "Float", span, depth),
+        Expression::Boolean(_) => push_line(out, source, "Boolean", span, depth),
+        Expression::Char(_) => push_line(out, source, "Char", span, depth),
+        Expression::String(_) => push_line(out, source, "String", span, depth),
+        Expression::Variable(_) => push_line(out, source, "Variable", span, depth),
+        Expression::Array(elements) => {
+            push_line(out, source, "Array", span, depth);
+            for element in elements {
+                write_expression(out, source, element, depth + 1);
+            }
+        }
+        Expression::Tuple(elements) => {
+            push_line(out, source, "Tuple", span, depth);
+            for element in elements {
+                write_expression(out, source, element, depth + 1);
+            }
+        }
+        Expression::Map(entries) => {
+            push_line(out, source, "Map", span, depth);
+            for (_, value) in entries {
+                write_expression(out, source, value, depth + 1);
+            }
+        }
+        Expression::Index { target, index } => {
+            push_line(out, source, "Index", span, depth);
+            write_expression(out, source, target, depth + 1);
+            write_expression(out, source, index, depth + 1);
+        }
+        Expression::Unary { operand, .. } => {
+            push_line(out, source, "Unary", span, depth);
+            write_expression(out, source, operand, depth + 1);
+        }
+        Expression::Binary { left, right, .. } => {
+            push_line(out, source, "Binary", span, depth);
+            write_expression(out, source, left, depth + 1);
+            write_expression(out, source, right, depth + 1);
+        }
+        Expression::FunctionCall { arguments, .. } => {
+            push_line(out, source, "FunctionCall", span, depth);
+            for argument in arguments {
+                write_expression(out, source, argument, depth + 1);
+            }
+        }
+        Expression::MethodCall {
+            receiver,
+            arguments,
+            ..
+        } => {
+            push_line(out, source, "MethodCall", span, depth);
+            write_expression(out, source, receiver, depth + 1);
+            for argument in arguments {
+                write_expression(out, source, argument, depth + 1);
+            }
+        }
+        Expression::Lambda { body, .. } => {
+            push_line(out, source, "Lambda", span, depth);
+            for statement in body {
+                write_statement(out, source, statement, depth + 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_parser::parse_program;
+
+    #[test]
+    fn a_binary_node_spans_exactly_its_operands() {
+        let program = parse_program("x = a + b;").unwrap();
+        let dump = dump_spans("x = a + b;", &program);
+        assert_eq!(
+            dump,
+            "Assignment[«x = a + b;»]\n  Binary[«a + b»]\n    Variable[«a»]\n    Variable[«b»]\n"
+        );
+    }
+
+    #[test]
+    fn nested_statements_indent_with_their_spans() {
+        let source = "if (x) { return 1; }";
+        let program = parse_program(source).unwrap();
+        let dump = dump_spans(source, &program);
+        assert!(dump.starts_with("If[«if (x) { return 1; }»]\n"));
+        assert!(dump.contains("  Return[«return 1;»]\n"));
+    }
+}
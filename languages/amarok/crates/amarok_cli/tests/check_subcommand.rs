@@ -0,0 +1,70 @@
+//! End-to-end checks of `amarok check` and the `--max-errors` cap.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarok-cli-check-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn clean_file_checks_ok() {
+    let script = write_script("clean.amarok", "x = 1;\nprint(x);\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("check")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(": ok"), "stdout was: {}", stdout);
+}
+
+#[test]
+fn max_errors_caps_the_diagnostics() {
+    // Thirty broken statements; recovery reports one error per statement.
+    let script = write_script("broken.amarok", &"x = ;\n".repeat(30));
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--error-format=json")
+        .arg("--max-errors=5")
+        .arg("check")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let diagnostics = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .count();
+    assert_eq!(diagnostics, 5, "stderr was: {}", stderr);
+    assert!(
+        stderr.contains("... and 25 more errors"),
+        "stderr was: {}",
+        stderr
+    );
+}
+
+#[test]
+fn all_errors_print_under_the_default_cap() {
+    let script = write_script("few.amarok", "x = ;\ny = ;\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--error-format=json")
+        .arg("check")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let diagnostics = stderr
+        .lines()
+        .filter(|line| line.starts_with('{'))
+        .count();
+    assert_eq!(diagnostics, 2, "stderr was: {}", stderr);
+    assert!(!stderr.contains("more errors"), "stderr was: {}", stderr);
+}
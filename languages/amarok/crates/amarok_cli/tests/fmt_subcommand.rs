@@ -0,0 +1,61 @@
+//! End-to-end checks of `amarok fmt`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarok-cli-fmt-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn fmt_prints_the_canonical_form_to_stdout() {
+    let script = write_script("messy.amarok", "if(x>0){print( 1 );}");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("fmt")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "if x > 0 {\n    print(1);\n}\n"
+    );
+    // Without --write the file is untouched.
+    assert_eq!(fs::read_to_string(&script).unwrap(), "if(x>0){print( 1 );}");
+}
+
+#[test]
+fn fmt_write_rewrites_in_place_and_is_idempotent() {
+    let script = write_script("rewrite.amarok", "x=1;while x<3 {x=x+1;}");
+    let run = || {
+        let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+            .arg("--write")
+            .arg("fmt")
+            .arg(&script)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        fs::read_to_string(&script).unwrap()
+    };
+    let once = run();
+    assert_eq!(once, "x = 1;\nwhile x < 3 {\n    x = x + 1;\n}\n");
+    assert_eq!(run(), once);
+}
+
+#[test]
+fn a_file_that_does_not_parse_is_left_alone() {
+    let script = write_script("broken.amarok", "def f( {");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--write")
+        .arg("fmt")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(fs::read_to_string(&script).unwrap(), "def f( {");
+}
@@ -0,0 +1,40 @@
+//! End-to-end checks of `--max-heap-bytes` against real script files.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarok-cli-heap-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn a_script_over_the_heap_limit_exits_nonzero() {
+    let script = write_script("hungry.amarok", "x = range(1000000);\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--max-heap-bytes=1024")
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("heap limit"), "stderr was: {}", stderr);
+}
+
+#[test]
+fn the_same_script_runs_without_the_flag() {
+    let script = write_script("hungry-ok.amarok", "print(len(range(1000000)));\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout, "1000000\n");
+}
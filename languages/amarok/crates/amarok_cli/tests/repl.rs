@@ -0,0 +1,37 @@
+//! End-to-end checks of the interactive REPL, driven over piped stdin.
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+fn drive_repl(input: &str) -> String {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("repl")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .as_mut()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn multi_statement_line_echoes_the_last_value() {
+    let stdout = drive_repl("a = 1; a + 1\nexit\n");
+    assert!(stdout.contains("2\n"), "stdout was: {}", stdout);
+}
+
+#[test]
+fn definitions_echo_nothing_but_persist_across_lines() {
+    let stdout = drive_repl("def double(x) { return x * 2; }\ndouble(21)\nexit\n");
+    // The definition line echoes nothing; the call on the next line echoes.
+    assert!(!stdout.contains("null"), "stdout was: {}", stdout);
+    assert!(stdout.contains("42\n"), "stdout was: {}", stdout);
+}
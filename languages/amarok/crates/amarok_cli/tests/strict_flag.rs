@@ -0,0 +1,73 @@
+//! End-to-end checks of `--strict`, which turns warnings into a failing exit.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarok-cli-strict-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+// Defining a function named `len` only warns about shadowing the builtin.
+const WARNING_ONLY: &str = "def len(x) { return 0; }\nprint(1);\n";
+
+#[test]
+fn a_warning_does_not_fail_the_run_by_default() {
+    let script = write_script("warn.amarok", WARNING_ONLY);
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("warning:"), "stderr was: {}", stderr);
+}
+
+#[test]
+fn strict_turns_the_same_warning_into_a_failure() {
+    let script = write_script("warn-strict.amarok", WARNING_ONLY);
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--strict")
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    // The warning itself is still rendered as a warning.
+    assert!(stderr.contains("warning:"), "stderr was: {}", stderr);
+    assert!(
+        stderr.contains("warning(s) treated as errors"),
+        "stderr was: {}",
+        stderr
+    );
+}
+
+#[test]
+fn the_werror_spelling_is_an_alias() {
+    let script = write_script("warn-werror.amarok", WARNING_ONLY);
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("-Werror")
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn strict_is_a_no_op_on_a_clean_script() {
+    let script = write_script("clean.amarok", "print(1);\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("--strict")
+        .arg("run")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
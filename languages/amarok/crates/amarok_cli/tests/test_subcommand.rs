@@ -0,0 +1,79 @@
+//! End-to-end checks of `amarok test` against real script files.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarok-cli-test-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn passing_assertions_exit_zero() {
+    let script = write_script("passes.amarok", "assert(1 < 2);\nassert_eq(2 + 2, 4);\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("test")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(": ok"), "stdout was: {}", stdout);
+}
+
+#[test]
+fn failing_assertion_exits_nonzero_with_its_location() {
+    let script = write_script("fails.amarok", "assert(1 < 2);\nassert(1 > 2);\n");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("test")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("assertion failed"), "stderr was: {}", stderr);
+    // The caret rendering points at line 2, where the failing assert sits.
+    assert!(stderr.contains(":2:"), "stderr was: {}", stderr);
+    assert!(stderr.contains(": FAILED"), "stderr was: {}", stderr);
+}
+
+#[test]
+fn matching_expect_comments_exit_zero() {
+    let script = write_script(
+        "golden.amarok",
+        "print(1 + 1); // expect: 2\nprint(\"hi\"); // expect: hi\n",
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("test")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(": ok"), "stdout was: {}", stdout);
+}
+
+#[test]
+fn a_mismatched_expect_comment_fails_with_both_lines() {
+    let script = write_script(
+        "golden-fails.amarok",
+        "print(1); // expect: 1\nprint(3); // expect: 2\n",
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_amarok"))
+        .arg("test")
+        .arg(&script)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("output line 2: expected `2`, got `3`"),
+        "stderr was: {}",
+        stderr
+    );
+    assert!(stderr.contains(": FAILED"), "stderr was: {}", stderr);
+}
@@ -0,0 +1,688 @@
+//! The default builtin functions every interpreter starts with.
+
+use crate::error::RuntimeError;
+use crate::interpreter::Interpreter;
+use crate::value::{format_value, format_value_with, Value};
+
+pub(crate) fn register_default_builtins(interpreter: &mut Interpreter) {
+    interpreter.register_builtin("print", |interpreter, arguments, _span| {
+        let numbers = interpreter.number_format();
+        let line = arguments
+            .iter()
+            .map(|argument| format_value_with(argument, numbers))
+            .collect::<Vec<_>>()
+            .join(" ");
+        interpreter.push_output(line);
+        if interpreter.events_captured() {
+            interpreter.push_output_event(arguments.to_vec());
+        }
+        Ok(Value::Null)
+    });
+
+    interpreter.register_builtin_with_arity("len", 1, 1, |_interpreter, arguments, span| {
+        match arguments {
+            [Value::String(s)] => Ok(Value::Integer(s.chars().count() as i64)),
+            [Value::Array(elements)] => Ok(Value::Integer(elements.borrow().len() as i64)),
+            [Value::Map(entries)] => Ok(Value::Integer(entries.borrow().len() as i64)),
+            [other] => Err(RuntimeError::new(
+                format!(
+                    "len() expects a string or collection, got {}",
+                    format_value(other)
+                ),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("map", 2, 2, |interpreter, arguments, span| {
+        match arguments {
+            [Value::Array(elements), Value::Function(function)] => {
+                // Clone the elements out of the borrow so the callback is
+                // free to touch the array itself.
+                let input: Vec<Value> = elements.borrow().clone();
+                let function = std::rc::Rc::clone(function);
+                let mut output = Vec::with_capacity(input.len());
+                for element in input {
+                    output.push(interpreter.call_value(&function, vec![element], span)?);
+                }
+                Ok(Value::array(output))
+            }
+            [_, _] => Err(RuntimeError::new(
+                "map() expects an array and a function",
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    // `range(end)`, `range(start, end)`, `range(start, end, step)` — integers
+    // from start (default 0) up to but excluding end, stepping by step
+    // (default 1). A negative step counts down; an exhausted range is empty.
+    interpreter.register_builtin_with_arity("range", 1, 3, |interpreter, arguments, span| {
+        let (start, end, step) = match arguments {
+            [Value::Integer(end)] => (0, *end, 1),
+            [Value::Integer(start), Value::Integer(end)] => (*start, *end, 1),
+            [Value::Integer(start), Value::Integer(end), Value::Integer(step)] => {
+                (*start, *end, *step)
+            }
+            [_] | [_, _] | [_, _, _] => {
+                return Err(RuntimeError::new("range() expects integer arguments", span))
+            }
+            _ => unreachable!("arity is checked in call_function"),
+        };
+        if step == 0 {
+            return Err(RuntimeError::new("range() step must not be zero", span));
+        }
+        let mut elements = Vec::new();
+        let mut current = start;
+        while (step > 0 && current < end) || (step < 0 && current > end) {
+            // Charged element by element so a huge range stops at the heap
+            // limit instead of allocating first.
+            interpreter.charge_allocation(std::mem::size_of::<Value>(), span)?;
+            elements.push(Value::Integer(current));
+            let Some(next) = current.checked_add(step) else {
+                break;
+            };
+            current = next;
+        }
+        Ok(Value::array(elements))
+    });
+
+    // Scope introspection, for a REPL's `:vars` and self-inspecting
+    // scripts: `vars()` lists every visible variable name — outermost scope
+    // first, insertion-ordered within a scope — and `has_var(name)` tests
+    // one name without the undefined-variable error a bare read raises.
+    interpreter.register_builtin_with_arity("vars", 0, 0, |interpreter, _arguments, _span| {
+        Ok(Value::array(
+            interpreter
+                .visible_variable_names()
+                .into_iter()
+                .map(Value::String)
+                .collect(),
+        ))
+    });
+
+    interpreter.register_builtin_with_arity("has_var", 1, 1, |interpreter, arguments, span| {
+        match arguments {
+            [Value::String(name)] => Ok(Value::Boolean(interpreter.has_variable(name))),
+            [other] => Err(RuntimeError::new(
+                format!("has_var() expects a string name, got {}", format_value(other)),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    // Assignment aliases collections; these two let scripts opt into copying.
+    // `copy` duplicates only the top level (nested collections stay shared),
+    // `deep_copy` duplicates all the way down. Scalars pass through either.
+    interpreter.register_builtin_with_arity("copy", 1, 1, |_interpreter, arguments, _span| {
+        match arguments {
+            [Value::Array(elements)] => Ok(Value::array(elements.borrow().clone())),
+            [Value::Map(entries)] => Ok(Value::map(entries.borrow().clone())),
+            [other] => Ok(other.clone()),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("deep_copy", 1, 1, |_interpreter, arguments, span| {
+        match arguments {
+            [value] => deep_copy_value(value, span, &mut Vec::new()),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    // Explicit shared mutable boxes: `cell(v)` wraps a value, `get`/`set`
+    // read and write it. Every variable holding the same cell sees the same
+    // slot, closures included. Like the collections, cells are reference
+    // counted, so a cycle built through a cell leaks.
+    interpreter.register_builtin_with_arity("cell", 1, 1, |_interpreter, arguments, _span| {
+        match arguments {
+            [value] => Ok(Value::cell(value.clone())),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("get", 1, 1, |_interpreter, arguments, span| {
+        match arguments {
+            [Value::Ref(inner)] => Ok(inner.borrow().clone()),
+            [other] => Err(RuntimeError::new(
+                format!("get() expects a cell, got {}", format_value(other)),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("set", 2, 2, |_interpreter, arguments, span| {
+        match arguments {
+            [Value::Ref(inner), value] => {
+                *inner.borrow_mut() = value.clone();
+                Ok(Value::Null)
+            }
+            [other, _] => Err(RuntimeError::new(
+                format!("set() expects a cell, got {}", format_value(other)),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    // Base formatters return bare digits without a `0x`/`0b`/`0o` prefix, with
+    // a leading `-` for negative values, so they round-trip through
+    // `parse_int`.
+    interpreter.register_builtin_with_arity("to_hex", 1, 1, |_interpreter, arguments, span| {
+        format_radix(arguments, span, "to_hex", |n| format!("{:x}", n))
+    });
+
+    interpreter.register_builtin_with_arity("to_bin", 1, 1, |_interpreter, arguments, span| {
+        format_radix(arguments, span, "to_bin", |n| format!("{:b}", n))
+    });
+
+    interpreter.register_builtin_with_arity("to_oct", 1, 1, |_interpreter, arguments, span| {
+        format_radix(arguments, span, "to_oct", |n| format!("{:o}", n))
+    });
+
+    interpreter.register_builtin_with_arity("parse_int", 2, 2, |_interpreter, arguments, span| {
+        let (text, base) = match arguments {
+            [Value::String(text), Value::Integer(base)] => (text, *base),
+            [_, _] => {
+                return Err(RuntimeError::new(
+                    "parse_int() expects a string and an integer base",
+                    span,
+                ))
+            }
+            _ => unreachable!("arity is checked in call_function"),
+        };
+        if !(2..=36).contains(&base) {
+            return Err(RuntimeError::new(
+                format!("parse_int() base must be between 2 and 36, got {}", base),
+                span,
+            ));
+        }
+        i64::from_str_radix(text, base as u32)
+            .map(Value::Integer)
+            .map_err(|_| {
+                RuntimeError::new(
+                    format!("parse_int(): invalid base-{} integer: {}", base, text),
+                    span,
+                )
+            })
+    });
+
+    interpreter.register_builtin_with_arity("round", 2, 2, |_interpreter, arguments, span| {
+        match arguments {
+            [Value::Float(x), Value::Integer(digits)] => {
+                let factor = 10f64.powi((*digits).clamp(-18, 18) as i32);
+                Ok(Value::Float((x * factor).round() / factor))
+            }
+            [Value::Integer(n), Value::Integer(_)] => Ok(Value::Float(*n as f64)),
+            [_, _] => Err(RuntimeError::new(
+                "round() expects a number and an integer digit count",
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("env", 1, 2, |interpreter, arguments, span| {
+        if !interpreter.env_allowed() {
+            return Err(RuntimeError::new(
+                "env() is disabled; the host must enable it with Interpreter::set_allow_env",
+                span,
+            ));
+        }
+        match arguments {
+            [Value::String(name)] => Ok(std::env::var(name)
+                .map(Value::String)
+                .unwrap_or(Value::Null)),
+            [Value::String(name), default] => Ok(std::env::var(name)
+                .map(Value::String)
+                .unwrap_or_else(|_| default.clone())),
+            [other] | [other, _] => Err(RuntimeError::new(
+                format!("env() expects a string name, got {}", format_value(other)),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("read_file", 1, 1, |interpreter, arguments, span| {
+        if !interpreter.fs_allowed() {
+            return Err(RuntimeError::new(
+                "filesystem access is disabled; the host must enable it with Interpreter::set_allow_fs",
+                span,
+            ));
+        }
+        match arguments {
+            [Value::String(path)] => std::fs::read_to_string(path)
+                .map(Value::String)
+                .map_err(|error| {
+                    RuntimeError::new(format!("cannot read {}: {}", path, error), span)
+                }),
+            [other] => Err(RuntimeError::new(
+                format!(
+                    "read_file() expects a string path, got {}",
+                    format_value(other)
+                ),
+                span,
+            )),
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("assert", 1, 1, |_interpreter, arguments, span| {
+        match arguments {
+            [value] => {
+                if value.is_truthy() {
+                    Ok(Value::Null)
+                } else {
+                    Err(RuntimeError::new("assertion failed", span))
+                }
+            }
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+
+    interpreter.register_builtin_with_arity("assert_eq", 2, 2, |_interpreter, arguments, span| {
+        match arguments {
+            [left, right] => {
+                if left == right {
+                    Ok(Value::Null)
+                } else {
+                    Err(RuntimeError::new(
+                        format!("assertion failed: {} != {}", left.repr(), right.repr()),
+                        span,
+                    ))
+                }
+            }
+            _ => unreachable!("arity is checked in call_function"),
+        }
+    });
+}
+
+/// Recursively copy a value. `visiting` holds the collections on the current
+/// path, so a self-referential structure errors instead of recursing forever.
+fn deep_copy_value(
+    value: &Value,
+    span: amarok_syntax::Span,
+    visiting: &mut Vec<*const ()>,
+) -> Result<Value, RuntimeError> {
+    use std::rc::Rc;
+    match value {
+        Value::Array(elements) => {
+            let pointer = Rc::as_ptr(elements) as *const ();
+            if visiting.contains(&pointer) {
+                return Err(RuntimeError::new(
+                    "deep_copy() cannot copy a self-referential collection",
+                    span,
+                ));
+            }
+            visiting.push(pointer);
+            let copied = elements
+                .borrow()
+                .iter()
+                .map(|element| deep_copy_value(element, span, visiting))
+                .collect::<Result<Vec<_>, _>>()?;
+            visiting.pop();
+            Ok(Value::array(copied))
+        }
+        Value::Map(entries) => {
+            let pointer = Rc::as_ptr(entries) as *const ();
+            if visiting.contains(&pointer) {
+                return Err(RuntimeError::new(
+                    "deep_copy() cannot copy a self-referential collection",
+                    span,
+                ));
+            }
+            visiting.push(pointer);
+            let copied = entries
+                .borrow()
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), deep_copy_value(value, span, visiting)?)))
+                .collect::<Result<Vec<_>, RuntimeError>>()?;
+            visiting.pop();
+            Ok(Value::map(copied))
+        }
+        Value::Ref(inner) => {
+            let pointer = Rc::as_ptr(inner) as *const ();
+            if visiting.contains(&pointer) {
+                return Err(RuntimeError::new(
+                    "deep_copy() cannot copy a self-referential collection",
+                    span,
+                ));
+            }
+            visiting.push(pointer);
+            let copied = deep_copy_value(&inner.borrow(), span, visiting)?;
+            visiting.pop();
+            Ok(Value::cell(copied))
+        }
+        other => Ok(other.clone()),
+    }
+}
+
+/// Shared argument handling for `to_hex`/`to_bin`/`to_oct`: one integer in,
+/// its magnitude formatted by `digits` with a `-` sign put back on.
+fn format_radix(
+    arguments: &[Value],
+    span: amarok_syntax::Span,
+    name: &str,
+    digits: impl Fn(u64) -> String,
+) -> Result<Value, RuntimeError> {
+    match arguments {
+        [Value::Integer(n)] => {
+            let sign = if *n < 0 { "-" } else { "" };
+            Ok(Value::String(format!("{}{}", sign, digits(n.unsigned_abs()))))
+        }
+        [other] => Err(RuntimeError::new(
+            format!("{}() expects an integer, got {}", name, format_value(other)),
+            span,
+        )),
+        _ => unreachable!("arity is checked in call_function"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_parser::parse_program;
+
+    fn run(source: &str) -> Result<Vec<String>, RuntimeError> {
+        let program = parse_program(source).expect("test programs should parse");
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program)?;
+        Ok(interpreter.output_lines().to_vec())
+    }
+
+    #[test]
+    fn print_joins_arguments_with_spaces() {
+        assert_eq!(run("print(1, \"x\", true);").unwrap(), vec!["1 x true"]);
+    }
+
+    #[test]
+    fn print_records_structured_events_when_capturing() {
+        let program = parse_program("print(1, \"x\");").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_capture_events(true);
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(
+            interpreter.output_events(),
+            [vec![Value::Integer(1), Value::String("x".to_string())]]
+        );
+        // The joined line is still produced alongside the record.
+        assert_eq!(interpreter.output_lines(), ["1 x"]);
+    }
+
+    #[test]
+    fn event_capture_is_off_by_default() {
+        let program = parse_program("print(1);").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        assert!(interpreter.output_events().is_empty());
+    }
+
+    #[test]
+    fn range_covers_each_arity() {
+        assert_eq!(run("print(range(4));").unwrap(), vec!["[0, 1, 2, 3]"]);
+        assert_eq!(run("print(range(2, 5));").unwrap(), vec!["[2, 3, 4]"]);
+        assert_eq!(run("print(range(1, 10, 3));").unwrap(), vec!["[1, 4, 7]"]);
+    }
+
+    #[test]
+    fn range_counts_down_with_a_negative_step() {
+        assert_eq!(
+            run("print(range(3, 0, 0 - 1));").unwrap(),
+            vec!["[3, 2, 1]"]
+        );
+    }
+
+    #[test]
+    fn an_exhausted_range_is_empty() {
+        assert_eq!(run("print(range(0));").unwrap(), vec!["[]"]);
+        assert_eq!(run("print(range(5, 2));").unwrap(), vec!["[]"]);
+    }
+
+    #[test]
+    fn a_zero_step_is_an_error() {
+        let error = run("range(0, 5, 0);").unwrap_err();
+        assert_eq!(error.message, "range() step must not be zero");
+    }
+
+    #[test]
+    fn len_of_string() {
+        assert_eq!(run("print(len(\"hello\"));").unwrap(), vec!["5"]);
+    }
+
+    #[test]
+    fn builtin_arity_is_checked_before_the_builtin_runs() {
+        let error = run("len();").unwrap_err();
+        assert_eq!(error.message, "len() expects 1 argument, got 0");
+        assert!(error.span.is_some());
+        let error = run("len(\"a\", \"b\");").unwrap_err();
+        assert_eq!(error.message, "len() expects 1 argument, got 2");
+    }
+
+    #[test]
+    fn arity_bounds_render_as_a_range() {
+        let error = run("range();").unwrap_err();
+        assert_eq!(error.message, "range() expects 1 to 3 arguments, got 0");
+        // The check runs even before env()'s permission gate.
+        let error = run("env();").unwrap_err();
+        assert_eq!(error.message, "env() expects 1 or 2 arguments, got 0");
+    }
+
+    #[test]
+    fn base_formatting() {
+        assert_eq!(
+            run("print(to_hex(255), to_bin(5), to_oct(8));").unwrap(),
+            vec!["ff 101 10"]
+        );
+        assert_eq!(run("print(to_hex(0 - 255));").unwrap(), vec!["-ff"]);
+    }
+
+    #[test]
+    fn parse_int_round_trips_and_rejects_bad_input() {
+        assert_eq!(run("print(parse_int(\"ff\", 16));").unwrap(), vec!["255"]);
+        assert_eq!(run("print(parse_int(\"-101\", 2));").unwrap(), vec!["-5"]);
+        let error = run("parse_int(\"xyz\", 10);").unwrap_err();
+        assert_eq!(error.message, "parse_int(): invalid base-10 integer: xyz");
+        assert!(error.span.is_some());
+        let error = run("to_hex(\"ff\");").unwrap_err();
+        assert_eq!(error.message, "to_hex() expects an integer, got ff");
+    }
+
+    fn run_with_env_allowed(source: &str) -> Result<Vec<String>, RuntimeError> {
+        let program = parse_program(source).expect("test programs should parse");
+        let mut interpreter = Interpreter::new();
+        interpreter.set_allow_env(true);
+        interpreter.run_program(&program)?;
+        Ok(interpreter.output_lines().to_vec())
+    }
+
+    #[test]
+    fn env_reads_a_set_variable() {
+        std::env::set_var("AMAROK_TEST_ENV", "forty-two");
+        assert_eq!(
+            run_with_env_allowed("print(env(\"AMAROK_TEST_ENV\"));").unwrap(),
+            vec!["forty-two"]
+        );
+    }
+
+    #[test]
+    fn env_returns_null_or_default_when_unset() {
+        assert_eq!(
+            run_with_env_allowed(
+                "print(env(\"AMAROK_TEST_ENV_UNSET\")); print(env(\"AMAROK_TEST_ENV_UNSET\", \"fallback\"));"
+            )
+            .unwrap(),
+            vec!["null", "fallback"]
+        );
+    }
+
+    fn run_with_fs_allowed(source: &str) -> Result<Vec<String>, RuntimeError> {
+        let program = parse_program(source).expect("test programs should parse");
+        let mut interpreter = Interpreter::new();
+        interpreter.set_allow_fs(true);
+        interpreter.run_program(&program)?;
+        Ok(interpreter.output_lines().to_vec())
+    }
+
+    #[test]
+    fn read_file_returns_the_contents() {
+        let path = std::env::temp_dir().join(format!("amarok-read-{}.txt", std::process::id()));
+        std::fs::write(&path, "hello from disk").unwrap();
+        let source = format!("print(read_file(\"{}\"));", path.display());
+        assert_eq!(
+            run_with_fs_allowed(&source).unwrap(),
+            vec!["hello from disk"]
+        );
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn read_file_errors_on_a_missing_file() {
+        let error =
+            run_with_fs_allowed("read_file(\"/definitely/not/a/real/path\");").unwrap_err();
+        assert!(error.message.starts_with("cannot read"));
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn read_file_is_denied_by_default() {
+        let error = run("read_file(\"anything\");").unwrap_err();
+        assert!(error.message.contains("filesystem access is disabled"));
+    }
+
+    #[test]
+    fn env_is_denied_by_default() {
+        let error = run("env(\"PATH\");").unwrap_err();
+        assert!(error.message.contains("env() is disabled"));
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn whole_floats_keep_their_decimal_point() {
+        assert_eq!(run("print(1.0);").unwrap(), vec!["1.0"]);
+        assert_eq!(run("print(2.5 * 2.0);").unwrap(), vec!["5.0"]);
+    }
+
+    #[test]
+    fn round_controls_precision() {
+        assert_eq!(run("print(round(3.14159, 2));").unwrap(), vec!["3.14"]);
+        assert_eq!(run("print(round(3.14159, 0));").unwrap(), vec!["3.0"]);
+    }
+
+    #[test]
+    fn round_rejects_non_numbers() {
+        let error = run(r#"round("pi", 2);"#).unwrap_err();
+        assert_eq!(
+            error.message,
+            "round() expects a number and an integer digit count"
+        );
+    }
+
+    #[test]
+    fn assignment_aliases_collections() {
+        assert_eq!(
+            run("a = [1]; b = a; b[0] = 2; print(a);").unwrap(),
+            vec!["[2]"]
+        );
+    }
+
+    #[test]
+    fn copy_isolates_the_top_level() {
+        assert_eq!(
+            run("a = [1]; b = copy(a); b[0] = 2; print(a, b);").unwrap(),
+            vec!["[1] [2]"]
+        );
+    }
+
+    #[test]
+    fn copy_is_shallow() {
+        assert_eq!(
+            run("a = [[1]]; b = copy(a); b[0][0] = 9; print(a);").unwrap(),
+            vec!["[[9]]"]
+        );
+    }
+
+    #[test]
+    fn deep_copy_isolates_nested_collections() {
+        assert_eq!(
+            run("a = {inner: [1]}; b = deep_copy(a); b[\"inner\"][0] = 9; print(a, b);").unwrap(),
+            vec!["{inner: [1]} {inner: [9]}"]
+        );
+    }
+
+    #[test]
+    fn deep_copy_rejects_a_self_referential_collection() {
+        let error = run("a = [1]; a[0] = a; deep_copy(a);").unwrap_err();
+        assert_eq!(
+            error.message,
+            "deep_copy() cannot copy a self-referential collection"
+        );
+    }
+
+    #[test]
+    fn a_shared_cell_is_one_slot() {
+        assert_eq!(
+            run("a = cell(1); b = a; set(b, 2); print(get(a), get(b));").unwrap(),
+            vec!["2 2"]
+        );
+    }
+
+    #[test]
+    fn closures_share_state_through_a_cell() {
+        assert_eq!(
+            run("c = cell(0); f = fn() { set(c, get(c) + 1); }; f(); f(); print(get(c));")
+                .unwrap(),
+            vec!["2"]
+        );
+    }
+
+    #[test]
+    fn a_cell_prints_its_contents() {
+        assert_eq!(run("print(cell(5));").unwrap(), vec!["cell(5)"]);
+    }
+
+    #[test]
+    fn get_and_set_reject_non_cells() {
+        let error = run("get(5);").unwrap_err();
+        assert_eq!(error.message, "get() expects a cell, got 5");
+        let error = run("set(5, 1);").unwrap_err();
+        assert_eq!(error.message, "set() expects a cell, got 5");
+    }
+
+    #[test]
+    fn map_applies_a_lambda_to_each_element() {
+        assert_eq!(
+            run("print(map([1, 2, 3], fn(x) { return x * 2; }));").unwrap(),
+            vec!["[2, 4, 6]"]
+        );
+    }
+
+    #[test]
+    fn map_rejects_a_non_function() {
+        let error = run("map([1], 2);").unwrap_err();
+        assert_eq!(error.message, "map() expects an array and a function");
+    }
+
+    #[test]
+    fn assert_passes_and_fails() {
+        assert!(run("assert(1 < 2);").is_ok());
+        let error = run("assert(1 > 2);").unwrap_err();
+        assert_eq!(error.message, "assertion failed");
+    }
+
+    #[test]
+    fn assert_eq_reports_both_values() {
+        let error = run("assert_eq(1, 2);").unwrap_err();
+        assert_eq!(error.message, "assertion failed: 1 != 2");
+    }
+
+    #[test]
+    fn assert_eq_quotes_strings_in_its_message() {
+        let error = run(r#"assert_eq("5", 5);"#).unwrap_err();
+        assert_eq!(error.message, "assertion failed: \"5\" != 5");
+    }
+}
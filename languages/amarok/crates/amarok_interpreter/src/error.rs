@@ -0,0 +1,59 @@
+use std::fmt;
+
+use amarok_syntax::Span;
+
+/// An error raised while executing a program.
+///
+/// Most errors point at the expression or statement that caused them; a few
+/// (like control flow escaping the top level) have no useful location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub message: String,
+    pub span: Option<Span>,
+}
+
+/// The mapping from error messages to stable public codes, matched by
+/// prefix. Codes never change meaning once assigned; new entries append to
+/// the table with the next number.
+const ERROR_CODES: &[(&str, &str)] = &[
+    ("Undefined variable:", "E0001"),
+    ("Undefined function:", "E0002"),
+    ("division by zero", "E0003"),
+    ("modulo by zero", "E0004"),
+    ("floor division by zero", "E0005"),
+    ("Index ", "E0006"),
+    ("Key not found:", "E0007"),
+    ("Unsupported operation:", "E0008"),
+    ("assertion failed", "E0009"),
+    ("Return outside of function", "E0010"),
+];
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span: Some(span),
+        }
+    }
+
+    /// The stable diagnostic code for this error, e.g. `E0003` for division
+    /// by zero, so docs and tooling can reference it across versions. Errors
+    /// outside the table — host builtin failures, say — have no code.
+    pub fn code(&self) -> Option<&'static str> {
+        ERROR_CODES
+            .iter()
+            .find(|(prefix, _)| self.message.starts_with(prefix))
+            .map(|(_, code)| *code)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.span {
+            Some(span) => write!(f, "{} at {}..{}", self.message, span.start, span.end),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {}
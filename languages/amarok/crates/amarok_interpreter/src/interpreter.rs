@@ -0,0 +1,2564 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use amarok_syntax::ast::{
+    deep_clone_statements, BinaryOperator, Expression, Program, Statement, UnaryOperator,
+};
+use amarok_syntax::{Span, Spanned};
+
+use crate::builtins;
+use crate::error::RuntimeError;
+use crate::symbol::{Symbol, SymbolTable};
+use crate::value::{format_value, FunctionValue, NumberFormat, Value};
+
+/// How a statement finished: normally, or by transferring control.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ControlFlow {
+    Normal,
+    Break,
+    Continue,
+    /// A `return`; the span points at the statement so that a return escaping
+    /// to the top level can be reported precisely.
+    Return(Value, Span),
+    /// `return f(args);` where `f` is the function currently executing: the
+    /// arguments are already evaluated and `call_function` rebinds and loops
+    /// instead of recursing, so self-recursion runs in constant stack.
+    TailCall(Vec<Value>),
+}
+
+/// A host-provided function callable from scripts.
+pub type BuiltinFunction =
+    Rc<dyn Fn(&mut Interpreter, &[Value], Span) -> Result<Value, RuntimeError>>;
+
+/// A registered builtin plus its optional arity bounds. The bounds are
+/// checked centrally in `call_function`, so every builtin that declares them
+/// gets the same "expects N arguments, got M" spanned error.
+pub(crate) struct Builtin {
+    function: BuiltinFunction,
+    /// Inclusive `(min, max)` argument counts; `None` accepts any count.
+    arity: Option<(usize, usize)>,
+}
+
+pub(crate) enum Function {
+    UserDefined {
+        parameters: Vec<String>,
+        body: Vec<Spanned<Statement>>,
+    },
+}
+
+/// A single scope, keyed on interned symbols so lookups along the scope
+/// chain compare integers rather than strings. Variables are kept in
+/// definition order so that anything enumerating them (debug dumps, a future
+/// `vars()` builtin) is deterministic.
+#[derive(Debug, Default)]
+pub(crate) struct Scope {
+    variables: Vec<(Symbol, Value)>,
+}
+
+impl Scope {
+    fn get(&self, symbol: Symbol) -> Option<&Value> {
+        self.variables
+            .iter()
+            .find(|(variable, _)| *variable == symbol)
+            .map(|(_, value)| value)
+    }
+
+    fn get_mut(&mut self, symbol: Symbol) -> Option<&mut Value> {
+        self.variables
+            .iter_mut()
+            .find(|(variable, _)| *variable == symbol)
+            .map(|(_, value)| value)
+    }
+
+    fn insert(&mut self, symbol: Symbol, value: Value) {
+        match self.get_mut(symbol) {
+            Some(slot) => *slot = value,
+            None => self.variables.push((symbol, value)),
+        }
+    }
+}
+
+/// A callback observing each statement as it starts executing.
+pub type StatementHook = Box<dyn FnMut(&Spanned<Statement>, &Interpreter)>;
+
+/// How deep block scopes may nest before execution stops with a
+/// `RuntimeError`. Every interpreted block costs several host stack frames,
+/// so the default has to fit a debug build on a 2 MiB thread with room to
+/// spare — far beyond hand-written code, which rarely nests past ten.
+/// Hosts can tighten or relax it with
+/// [`Interpreter::set_max_scope_depth`].
+pub const DEFAULT_MAX_SCOPE_DEPTH: usize = 64;
+
+pub struct Interpreter {
+    scopes: Vec<Scope>,
+    functions: HashMap<String, Function>,
+    builtins: HashMap<String, Builtin>,
+    output: Vec<String>,
+    /// One record per `print` call, holding the argument values themselves;
+    /// only filled when the host opts in with `set_capture_events`.
+    output_events: Vec<Vec<Value>>,
+    capture_events: bool,
+    /// Names of user functions currently executing, innermost last; used to
+    /// recognize self tail calls.
+    call_stack: Vec<String>,
+    /// Non-fatal issues noticed while running, e.g. shadowing a builtin.
+    /// Execution continues; hosts can surface these after the run.
+    warnings: Vec<RuntimeError>,
+    /// How `print` renders integer and float digits. Plain by default.
+    number_format: NumberFormat,
+    /// Byte-length cap on strings built by `+` and `*`; `None` (the
+    /// default) means unlimited.
+    max_string_len: Option<usize>,
+    /// Cap on block-scope nesting; see [`Interpreter::set_max_scope_depth`].
+    max_scope_depth: usize,
+    /// Approximate allocation budget; see
+    /// [`Interpreter::set_max_heap_bytes`]. `None` (the default) means
+    /// unlimited.
+    max_heap_bytes: Option<usize>,
+    /// Approximate bytes charged so far for strings and collections the
+    /// program has built.
+    allocated_bytes: usize,
+    /// When set, every assignment and function call is logged to
+    /// `trace_lines`. Off by default.
+    trace: bool,
+    trace_lines: Vec<String>,
+    /// When set, static lints run over each program before execution and
+    /// their findings land in `warnings`. Off by default.
+    lint: bool,
+    on_statement: Option<StatementHook>,
+    allow_env: bool,
+    allow_fs: bool,
+    /// Spent argument vectors, kept around so a call can reuse one instead
+    /// of allocating; it only grows to the deepest call nesting seen.
+    argument_pool: Vec<Vec<Value>>,
+    /// Interned identifiers; scopes key on these instead of strings.
+    symbols: SymbolTable,
+    /// Set (from any thread, e.g. a signal handler) to stop execution with an
+    /// "interrupted" error at the next statement boundary.
+    interrupt: Arc<AtomicBool>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interpreter {
+    pub fn new() -> Self {
+        let mut interpreter = Self {
+            scopes: vec![Scope::default()],
+            functions: HashMap::new(),
+            builtins: HashMap::new(),
+            output: Vec::new(),
+            output_events: Vec::new(),
+            capture_events: false,
+            call_stack: Vec::new(),
+            warnings: Vec::new(),
+            number_format: NumberFormat::Plain,
+            max_string_len: None,
+            max_scope_depth: DEFAULT_MAX_SCOPE_DEPTH,
+            max_heap_bytes: None,
+            allocated_bytes: 0,
+            trace: false,
+            trace_lines: Vec::new(),
+            lint: false,
+            on_statement: None,
+            allow_env: false,
+            allow_fs: false,
+            argument_pool: Vec::new(),
+            symbols: SymbolTable::default(),
+            interrupt: Arc::new(AtomicBool::new(false)),
+        };
+        builtins::register_default_builtins(&mut interpreter);
+        interpreter
+    }
+
+    /// Make `function` callable from scripts under `name`, replacing any
+    /// builtin previously registered under the same name.
+    ///
+    /// A panic inside the builtin is caught at the call boundary and
+    /// surfaced as a spanned "builtin 'name' panicked" runtime error, so a
+    /// misbehaving plugin cannot abort the host. The builtin should be
+    /// unwind-safe: any state it shares with the host may be mid-update when
+    /// it panics.
+    pub fn register_builtin(
+        &mut self,
+        name: &str,
+        function: impl Fn(&mut Interpreter, &[Value], Span) -> Result<Value, RuntimeError> + 'static,
+    ) {
+        self.builtins.insert(
+            name.to_string(),
+            Builtin {
+                function: Rc::new(function),
+                arity: None,
+            },
+        );
+    }
+
+    /// Like [`Interpreter::register_builtin`], with inclusive bounds on the
+    /// argument count. Calls outside the bounds are rejected before the
+    /// builtin runs, with a uniform "expects N arguments, got M" error, so
+    /// the builtin's body only ever sees an in-range slice.
+    pub fn register_builtin_with_arity(
+        &mut self,
+        name: &str,
+        min_args: usize,
+        max_args: usize,
+        function: impl Fn(&mut Interpreter, &[Value], Span) -> Result<Value, RuntimeError> + 'static,
+    ) {
+        self.builtins.insert(
+            name.to_string(),
+            Builtin {
+                function: Rc::new(function),
+                arity: Some((min_args, max_args)),
+            },
+        );
+    }
+
+    /// Execute every statement in `program`.
+    pub fn run_program(&mut self, program: &Program) -> Result<(), RuntimeError> {
+        if self.lint {
+            self.warnings.extend(crate::lint::lint_program(program));
+        }
+        match self.execute_statement_list(&program.statements)? {
+            ControlFlow::Normal => Ok(()),
+            ControlFlow::Return(_, span) => Err(RuntimeError::new(
+                "Return outside of function",
+                span,
+            )),
+            ControlFlow::Break => Err(RuntimeError {
+                message: "'break' outside of a loop".to_string(),
+                span: None,
+            }),
+            ControlFlow::Continue => Err(RuntimeError {
+                message: "'continue' outside of a loop".to_string(),
+                span: None,
+            }),
+            ControlFlow::TailCall(_) => {
+                unreachable!("tail calls only arise inside call_function")
+            }
+        }
+    }
+
+    /// Run `program` and produce the value of its final expression statement,
+    /// if any — the REPL uses this to echo results.
+    pub fn eval_program(&mut self, program: &Program) -> Result<Option<Value>, RuntimeError> {
+        let mut last_value = None;
+        for statement in &program.statements {
+            if let Statement::Expression(expression) = &statement.value {
+                last_value = Some(self.evaluate_expression(expression)?);
+            } else {
+                last_value = None;
+                match self.execute_statement(statement)? {
+                    ControlFlow::Normal => {}
+                    ControlFlow::Return(_, span) => {
+                        return Err(RuntimeError::new("Return outside of function", span))
+                    }
+                    ControlFlow::Break | ControlFlow::Continue => {
+                        return Err(RuntimeError {
+                            message: "loop control outside of a loop".to_string(),
+                            span: None,
+                        })
+                    }
+                    ControlFlow::TailCall(_) => {
+                        unreachable!("tail calls only arise inside call_function")
+                    }
+                }
+            }
+        }
+        Ok(last_value)
+    }
+
+    /// Execute one statement against the interpreter's persistent state —
+    /// the building block for a REPL or an embedder feeding statements
+    /// incrementally. An expression statement yields its value for echoing;
+    /// every other statement yields `None`. Control flow that has nowhere to
+    /// go at the top level (`return`, `break`, `continue`) is an error.
+    pub fn run_statement(
+        &mut self,
+        statement: &Spanned<Statement>,
+    ) -> Result<Option<Value>, RuntimeError> {
+        if let Statement::Expression(expression) = &statement.value {
+            return Ok(Some(self.evaluate_expression(expression)?));
+        }
+        match self.execute_statement(statement)? {
+            ControlFlow::Normal => Ok(None),
+            ControlFlow::Return(_, span) => {
+                Err(RuntimeError::new("Return outside of function", span))
+            }
+            ControlFlow::Break | ControlFlow::Continue => Err(RuntimeError {
+                message: "loop control outside of a loop".to_string(),
+                span: None,
+            }),
+            ControlFlow::TailCall(_) => {
+                unreachable!("tail calls only arise inside call_function")
+            }
+        }
+    }
+
+    /// Everything `print` has produced so far, one line per call.
+    pub fn output_lines(&self) -> &[String] {
+        &self.output
+    }
+
+    /// Record each `print` call's argument values in `output_events`, for
+    /// harnesses that want the values rather than a joined line. Off by
+    /// default; `output_lines` keeps working either way.
+    pub fn set_capture_events(&mut self, capture: bool) {
+        self.capture_events = capture;
+    }
+
+    /// One record per `print` call made while event capture was on, in
+    /// order, each holding that call's argument values.
+    pub fn output_events(&self) -> &[Vec<Value>] {
+        &self.output_events
+    }
+
+    /// Non-fatal issues noticed so far, in the order they occurred.
+    pub fn warnings(&self) -> &[RuntimeError] {
+        &self.warnings
+    }
+
+    /// Choose how `print` renders integer and float digits — e.g.
+    /// [`NumberFormat::Grouped`] for thousands separators. Purely an output
+    /// change: values, arithmetic, and `repr` are untouched.
+    pub fn set_number_format(&mut self, format: NumberFormat) {
+        self.number_format = format;
+    }
+
+    pub(crate) fn number_format(&self) -> NumberFormat {
+        self.number_format
+    }
+
+    /// Cap block-scope nesting at `depth` frames, so pathologically nested
+    /// blocks raise a `RuntimeError` instead of overflowing the host stack.
+    /// The default, [`DEFAULT_MAX_SCOPE_DEPTH`], is far beyond any
+    /// hand-written program. Function calls are unaffected: a call parks the
+    /// caller's frames, so its depth restarts near the root.
+    pub fn set_max_scope_depth(&mut self, depth: usize) {
+        self.max_scope_depth = depth;
+    }
+
+    /// Cap the approximate bytes of strings and collections the program may
+    /// build; `None` (the default) means unlimited. The accounting counts
+    /// allocations — array, tuple, and map literals, built strings, and
+    /// `range` — and never credits anything back when values are dropped,
+    /// so it bounds the total a script can ever allocate, not its live set.
+    pub fn set_max_heap_bytes(&mut self, limit: Option<usize>) {
+        self.max_heap_bytes = limit;
+    }
+
+    /// Approximate bytes charged so far; see
+    /// [`Interpreter::set_max_heap_bytes`] for what is counted.
+    pub fn allocated_bytes(&self) -> usize {
+        self.allocated_bytes
+    }
+
+    /// Cap the byte length of strings built by `+` concatenation and `*`
+    /// repetition, so untrusted input can't OOM the host by doubling a
+    /// string in a loop. The check runs on the would-be result's length
+    /// before anything is allocated. `None` (the default) is unlimited.
+    pub fn set_max_string_len(&mut self, limit: Option<usize>) {
+        self.max_string_len = limit;
+    }
+
+    /// Log each assignment and function call to the trace buffer — a
+    /// teaching and debugging aid. Off by default.
+    pub fn set_trace(&mut self, trace: bool) {
+        self.trace = trace;
+    }
+
+    /// Run the static lints in [`crate::lint`] over each program before it
+    /// executes, reporting findings through `warnings`. Off by default.
+    pub fn set_lint(&mut self, lint: bool) {
+        self.lint = lint;
+    }
+
+    /// A handle that, once stored `true` — typically from a Ctrl-C handler —
+    /// makes the interpreter stop at the next statement boundary with an
+    /// "interrupted" error. The flag clears when the error is raised, so the
+    /// same interpreter can keep serving a REPL session afterwards.
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.interrupt)
+    }
+
+    fn check_interrupt(&self, span: Span) -> Result<(), RuntimeError> {
+        if self.interrupt.load(Ordering::Relaxed) {
+            self.interrupt.store(false, Ordering::Relaxed);
+            return Err(RuntimeError::new("interrupted", span));
+        }
+        Ok(())
+    }
+
+    /// The execution trace recorded so far, one line per event, e.g.
+    /// `assign x = 3 at 0..6` and `call add(2, 3) -> 5 at 11..20`.
+    pub fn trace_lines(&self) -> &[String] {
+        &self.trace_lines
+    }
+
+    /// The variables of the innermost scope, in definition order.
+    pub fn debug_dump_scope(&self) -> Vec<(String, Value)> {
+        self.scopes
+            .last()
+            .expect("there is always at least one scope")
+            .variables
+            .iter()
+            .map(|(symbol, value)| (self.symbols.name(*symbol).to_string(), value.clone()))
+            .collect()
+    }
+
+    /// Install a hook fired at the start of every statement — the foundation
+    /// for tracers and step debuggers. Replaces any previous hook.
+    pub fn set_statement_hook(
+        &mut self,
+        hook: impl FnMut(&Spanned<Statement>, &Interpreter) + 'static,
+    ) {
+        self.on_statement = Some(Box::new(hook));
+    }
+
+    /// Remove the statement hook, if any.
+    pub fn clear_statement_hook(&mut self) {
+        self.on_statement = None;
+    }
+
+    /// Allow or deny the `env()` builtin. Denied by default, so embedders
+    /// must opt in before scripts can read the process environment.
+    pub fn set_allow_env(&mut self, allow: bool) {
+        self.allow_env = allow;
+    }
+
+    pub(crate) fn env_allowed(&self) -> bool {
+        self.allow_env
+    }
+
+    /// Allow or deny the `read_file()` builtin. Denied by default, so
+    /// untrusted scripts can't touch the filesystem.
+    pub fn set_allow_fs(&mut self, allow: bool) {
+        self.allow_fs = allow;
+    }
+
+    pub(crate) fn fs_allowed(&self) -> bool {
+        self.allow_fs
+    }
+
+    pub(crate) fn push_output(&mut self, line: String) {
+        self.output.push(line);
+    }
+
+    pub(crate) fn events_captured(&self) -> bool {
+        self.capture_events
+    }
+
+    pub(crate) fn push_output_event(&mut self, values: Vec<Value>) {
+        self.output_events.push(values);
+    }
+
+    fn execute_statement_list(
+        &mut self,
+        statements: &[Spanned<Statement>],
+    ) -> Result<ControlFlow, RuntimeError> {
+        for statement in statements {
+            let flow = self.execute_statement(statement)?;
+            if flow != ControlFlow::Normal {
+                return Ok(flow);
+            }
+        }
+        Ok(ControlFlow::Normal)
+    }
+
+    fn execute_statement(
+        &mut self,
+        statement: &Spanned<Statement>,
+    ) -> Result<ControlFlow, RuntimeError> {
+        self.check_interrupt(statement.span)?;
+        // Take the hook out so it can borrow the interpreter immutably while
+        // we still hold `&mut self`.
+        if let Some(mut hook) = self.on_statement.take() {
+            hook(statement, self);
+            self.on_statement = Some(hook);
+        }
+        match &statement.value {
+            // Run the synthesized statement; an error inside it names the
+            // syntax the user actually wrote.
+            Statement::Desugared {
+                original,
+                statement: synthesized,
+            } => {
+                self.execute_statement(synthesized).map_err(|mut error| {
+                    error.message =
+                        format!("{} (in expansion of `{}`)", error.message, original);
+                    error
+                })
+            }
+            Statement::Assignment { name, value } => {
+                let value = self.evaluate_expression(value)?;
+                if self.trace {
+                    self.trace_lines.push(format!(
+                        "assign {} = {} at {}",
+                        name,
+                        value.repr(),
+                        statement.span
+                    ));
+                }
+                self.assign_variable(name, value);
+                Ok(ControlFlow::Normal)
+            }
+            Statement::Let {
+                name,
+                type_annotation,
+                value,
+            } => {
+                let value = self.evaluate_expression(value)?;
+                if let Some(expected) = type_annotation {
+                    if value.type_name() != expected.name() {
+                        return Err(RuntimeError::new(
+                            format!(
+                                "`{}` is declared {} but its value is {}",
+                                name,
+                                expected.name(),
+                                value.type_name()
+                            ),
+                            statement.span,
+                        ));
+                    }
+                }
+                if self.trace {
+                    self.trace_lines.push(format!(
+                        "let {} = {} at {}",
+                        name,
+                        value.repr(),
+                        statement.span
+                    ));
+                }
+                // Unlike plain assignment, `let` always declares in the
+                // innermost scope, shadowing any outer binding.
+                if name != "_" {
+                    let symbol = self.symbols.intern(name);
+                    self.scopes
+                        .last_mut()
+                        .expect("there is always at least one scope")
+                        .insert(symbol, value);
+                }
+                Ok(ControlFlow::Normal)
+            }
+            Statement::IndexAssignment {
+                target,
+                index,
+                value,
+            } => {
+                let target_value = self.evaluate_expression(target)?;
+                let index_value = self.evaluate_expression(index)?;
+                let new_value = self.evaluate_expression(value)?;
+                match (target_value, index_value) {
+                    (Value::Array(elements), Value::Integer(i)) => {
+                        let mut elements = elements.borrow_mut();
+                        let length = elements.len();
+                        let slot = usize::try_from(i)
+                            .ok()
+                            .and_then(|i| elements.get_mut(i))
+                            .ok_or_else(|| {
+                                RuntimeError::new(
+                                    format!("Index {} out of bounds (length {})", i, length),
+                                    statement.span,
+                                )
+                            })?;
+                        *slot = new_value;
+                    }
+                    (Value::Map(entries), Value::String(key)) => {
+                        let mut entries = entries.borrow_mut();
+                        match entries.iter_mut().find(|(existing, _)| *existing == key) {
+                            Some((_, slot)) => *slot = new_value,
+                            None => entries.push((key, new_value)),
+                        }
+                    }
+                    (target_value, index_value) => {
+                        return Err(RuntimeError::new(
+                            format!(
+                                "Cannot index-assign {:?} with {:?}",
+                                target_value, index_value
+                            ),
+                            statement.span,
+                        ))
+                    }
+                }
+                Ok(ControlFlow::Normal)
+            }
+            Statement::FunctionDefinition {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                if self.builtins.contains_key(name) {
+                    self.warnings.push(RuntimeError::new(
+                        format!("function '{}' shadows the builtin of the same name", name),
+                        statement.span,
+                    ));
+                }
+                self.functions.insert(
+                    name.clone(),
+                    Function::UserDefined {
+                        parameters: parameters.clone(),
+                        // An iterative clone: script-supplied expressions can
+                        // be deep enough to overflow the derived `Clone`.
+                        body: deep_clone_statements(body),
+                    },
+                );
+                Ok(ControlFlow::Normal)
+            }
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let condition = self.evaluate_expression(condition)?;
+                if condition.is_truthy() {
+                    self.execute_block(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.execute_block(else_branch)
+                } else {
+                    Ok(ControlFlow::Normal)
+                }
+            }
+            Statement::Match { scrutinee, arms } => {
+                let scrutinee = self.evaluate_expression(scrutinee)?;
+                for arm in arms {
+                    let matched = match &arm.pattern {
+                        Some(pattern) => self.evaluate_expression(pattern)? == scrutinee,
+                        None => true,
+                    };
+                    if matched {
+                        return self.execute_block(&arm.body);
+                    }
+                }
+                // No arm matched and there was no `_`: a no-op, like an
+                // `if` without an `else`.
+                Ok(ControlFlow::Normal)
+            }
+            Statement::While {
+                condition,
+                body,
+                else_branch,
+            } => {
+                let mut ran_body = false;
+                loop {
+                    // An empty body never reaches `execute_statement`, so the
+                    // loop itself must notice an interrupt.
+                    self.check_interrupt(statement.span)?;
+                    let condition = self.evaluate_expression(condition)?;
+                    if !condition.is_truthy() {
+                        break;
+                    }
+                    ran_body = true;
+                    match self.execute_block(body)? {
+                        ControlFlow::Normal | ControlFlow::Continue => {}
+                        ControlFlow::Break => break,
+                        flow @ (ControlFlow::Return(..) | ControlFlow::TailCall(_)) => {
+                            return Ok(flow)
+                        }
+                    }
+                }
+                if !ran_body {
+                    if let Some(else_branch) = else_branch {
+                        return self.execute_block(else_branch);
+                    }
+                }
+                Ok(ControlFlow::Normal)
+            }
+            Statement::ForIn {
+                variable,
+                iterable,
+                body,
+            } => {
+                let iterable_value = self.evaluate_expression(iterable)?;
+                // Clone the items out up front so the body is free to mutate
+                // the collection it iterates.
+                let items: Vec<Value> = match &iterable_value {
+                    Value::Array(elements) => elements.borrow().clone(),
+                    Value::String(text) => text.chars().map(Value::Char).collect(),
+                    Value::Map(entries) => entries
+                        .borrow()
+                        .iter()
+                        .map(|(key, _)| Value::String(key.clone()))
+                        .collect(),
+                    other => {
+                        return Err(RuntimeError::new(
+                            format!("Cannot iterate over {}", format_value(other)),
+                            iterable.span,
+                        ))
+                    }
+                };
+                let symbol = self.symbols.intern(variable);
+                for item in items {
+                    self.enter_scope()?;
+                    self.scopes
+                        .last_mut()
+                        .expect("there is always at least one scope")
+                        .insert(symbol, item);
+                    let flow = self.execute_statement_list(body);
+                    self.exit_scope();
+                    match flow? {
+                        ControlFlow::Normal | ControlFlow::Continue => {}
+                        ControlFlow::Break => break,
+                        flow @ (ControlFlow::Return(..) | ControlFlow::TailCall(_)) => {
+                            return Ok(flow)
+                        }
+                    }
+                }
+                Ok(ControlFlow::Normal)
+            }
+            Statement::Return { value } => {
+                // `return f(args);` for the executing function is a tail
+                // call: evaluate the arguments and let `call_function` loop.
+                if let Some(Expression::FunctionCall { name, arguments }) =
+                    value.as_ref().map(|expression| &expression.value)
+                {
+                    if self.is_self_tail_call(name, arguments.len()) {
+                        let mut values = Vec::with_capacity(arguments.len());
+                        for argument in arguments {
+                            values.push(self.evaluate_expression(argument)?);
+                        }
+                        return Ok(ControlFlow::TailCall(values));
+                    }
+                }
+                let value = match value {
+                    Some(expression) => self.evaluate_expression(expression)?,
+                    None => Value::Null,
+                };
+                Ok(ControlFlow::Return(value, statement.span))
+            }
+            Statement::Break => Ok(ControlFlow::Break),
+            Statement::Continue => Ok(ControlFlow::Continue),
+            Statement::Block(statements) => self.execute_block(statements),
+            Statement::Expression(expression) => {
+                self.evaluate_expression(expression)?;
+                Ok(ControlFlow::Normal)
+            }
+        }
+    }
+
+    fn execute_block(
+        &mut self,
+        statements: &[Spanned<Statement>],
+    ) -> Result<ControlFlow, RuntimeError> {
+        self.enter_scope()?;
+        let flow = self.execute_statement_list(statements);
+        self.exit_scope();
+        flow
+    }
+
+    /// Push a fresh scope, refusing once nesting reaches the configured
+    /// limit. Call paths park the caller's frames and use [`Self::push_scope`]
+    /// instead: an early return here would skip their restore step.
+    fn enter_scope(&mut self) -> Result<(), RuntimeError> {
+        if self.scopes.len() >= self.max_scope_depth {
+            return Err(RuntimeError {
+                message: format!(
+                    "blocks nested deeper than the scope limit ({})",
+                    self.max_scope_depth
+                ),
+                span: None,
+            });
+        }
+        self.push_scope();
+        Ok(())
+    }
+
+    fn push_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn exit_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    /// Is a call to `name` with `arity` arguments a tail call of the function
+    /// currently executing? Only then is rebind-and-loop safe: the callee is
+    /// the same user-defined function and the argument count matches.
+    fn is_self_tail_call(&self, name: &str, arity: usize) -> bool {
+        if self.call_stack.last().map(String::as_str) != Some(name) {
+            return false;
+        }
+        matches!(
+            self.functions.get(name),
+            Some(Function::UserDefined { parameters, .. }) if parameters.len() == arity
+        )
+    }
+
+    /// Install a user-defined function from a host-built AST, as if a `def`
+    /// with this name had executed. Embedders use this to compose a function
+    /// library without running a program. Parameter names must be unique,
+    /// except that the discard `_` may repeat.
+    pub fn define_function(
+        &mut self,
+        name: &str,
+        parameters: Vec<String>,
+        body: Vec<Spanned<Statement>>,
+    ) -> Result<(), RuntimeError> {
+        for (position, parameter) in parameters.iter().enumerate() {
+            if parameter != "_" && parameters[..position].contains(parameter) {
+                return Err(RuntimeError {
+                    message: format!(
+                        "duplicate parameter `{}` in function '{}'",
+                        parameter, name
+                    ),
+                    span: None,
+                });
+            }
+        }
+        self.functions
+            .insert(name.to_string(), Function::UserDefined { parameters, body });
+        Ok(())
+    }
+
+    /// Seed the outermost scope with a host-provided value, e.g. `VERSION`.
+    /// Call before running a program; the script sees an ordinary variable
+    /// that it can read, reassign, or shadow with a parameter.
+    pub fn set_global(&mut self, name: &str, value: Value) {
+        let symbol = self.symbols.intern(name);
+        self.scopes
+            .first_mut()
+            .expect("there is always at least one scope")
+            .insert(symbol, value);
+    }
+
+    fn assign_variable(&mut self, name: &str, value: Value) {
+        // `_` is the discard pattern: the value was evaluated for its side
+        // effects and nothing is bound.
+        if name == "_" {
+            return;
+        }
+        let symbol = self.symbols.intern(name);
+        for scope in self.scopes.iter_mut().rev() {
+            if let Some(slot) = scope.get_mut(symbol) {
+                *slot = value;
+                return;
+            }
+        }
+        self.scopes
+            .last_mut()
+            .expect("there is always at least one scope")
+            .insert(symbol, value);
+    }
+
+    fn read_variable(&self, name: &str, span: Span) -> Result<Value, RuntimeError> {
+        if name == "_" {
+            return Err(RuntimeError::new("cannot read the discard pattern `_`", span));
+        }
+        // A name that was never interned was never bound, so the miss is
+        // answered without touching the scope chain (or growing the table).
+        if let Some(symbol) = self.symbols.lookup(name) {
+            for scope in self.scopes.iter().rev() {
+                if let Some(value) = scope.get(symbol) {
+                    return Ok(value.clone());
+                }
+            }
+        }
+        Err(RuntimeError::new(
+            format!("Undefined variable: {}", name),
+            span,
+        ))
+    }
+
+    /// Every variable name visible from the current scope, for the `vars()`
+    /// builtin: outermost scope first, insertion-ordered within a scope, and
+    /// a shadowed name appears once.
+    pub(crate) fn visible_variable_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for scope in &self.scopes {
+            for (symbol, _) in &scope.variables {
+                let name = self.symbols.name(*symbol);
+                if !names.iter().any(|existing| existing == name) {
+                    names.push(name.to_string());
+                }
+            }
+        }
+        names
+    }
+
+    /// Is `name` bound anywhere on the current scope chain?
+    pub(crate) fn has_variable(&self, name: &str) -> bool {
+        self.symbols
+            .lookup(name)
+            .is_some_and(|symbol| self.scopes.iter().any(|scope| scope.get(symbol).is_some()))
+    }
+
+    pub(crate) fn evaluate_expression(
+        &mut self,
+        expression: &Spanned<Expression>,
+    ) -> Result<Value, RuntimeError> {
+        match &expression.value {
+            Expression::Null => Ok(Value::Null),
+            Expression::Integer(value) => Ok(Value::Integer(*value)),
+            Expression::Float(value) => Ok(Value::Float(*value)),
+            Expression::Boolean(value) => Ok(Value::Boolean(*value)),
+            Expression::Char(value) => Ok(Value::Char(*value)),
+            Expression::String(value) => Ok(Value::String(value.clone())),
+            Expression::Variable(name) => self.read_variable(name, expression.span),
+            Expression::Array(elements) => {
+                self.charge_allocation(
+                    elements.len() * std::mem::size_of::<Value>(),
+                    expression.span,
+                )?;
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate_expression(element)?);
+                }
+                Ok(Value::array(values))
+            }
+            // There is no distinct tuple value yet; a tuple evaluates to an
+            // array, so indexing and `len` work on it unchanged.
+            Expression::Tuple(elements) => {
+                self.charge_allocation(
+                    elements.len() * std::mem::size_of::<Value>(),
+                    expression.span,
+                )?;
+                let mut values = Vec::with_capacity(elements.len());
+                for element in elements {
+                    values.push(self.evaluate_expression(element)?);
+                }
+                Ok(Value::array(values))
+            }
+            Expression::Map(entries) => {
+                self.charge_allocation(
+                    entries
+                        .iter()
+                        .map(|(key, _)| key.len() + std::mem::size_of::<Value>())
+                        .sum(),
+                    expression.span,
+                )?;
+                let mut values: Vec<(String, Value)> = Vec::with_capacity(entries.len());
+                for (key, expression) in entries {
+                    let value = self.evaluate_expression(expression)?;
+                    // A repeated literal key overwrites the earlier entry.
+                    match values.iter_mut().find(|(existing, _)| existing == key) {
+                        Some((_, slot)) => *slot = value,
+                        None => values.push((key.clone(), value)),
+                    }
+                }
+                Ok(Value::map(values))
+            }
+            Expression::Index { target, index } => {
+                let target_value = self.evaluate_expression(target)?;
+                let index_value = self.evaluate_expression(index)?;
+                self.evaluate_index(target_value, index_value, index.span, expression.span)
+            }
+            Expression::Unary { operator, operand } => {
+                let value = self.evaluate_expression(operand)?;
+                match operator {
+                    UnaryOperator::Negate => match value {
+                        Value::Integer(n) => Ok(Value::Integer(-n)),
+                        other => Err(RuntimeError::new(
+                            format!("Cannot negate {:?}", other),
+                            operand.span,
+                        )),
+                    },
+                    UnaryOperator::Not => Ok(Value::Boolean(!value.is_truthy())),
+                }
+            }
+            Expression::Binary {
+                left,
+                operator: BinaryOperator::And,
+                right,
+            } => {
+                let left = self.evaluate_expression(left)?;
+                if !left.is_truthy() {
+                    return Ok(Value::Boolean(false));
+                }
+                let right = self.evaluate_expression(right)?;
+                Ok(Value::Boolean(right.is_truthy()))
+            }
+            Expression::Binary {
+                left,
+                operator: BinaryOperator::Or,
+                right,
+            } => {
+                let left = self.evaluate_expression(left)?;
+                if left.is_truthy() {
+                    return Ok(Value::Boolean(true));
+                }
+                let right = self.evaluate_expression(right)?;
+                Ok(Value::Boolean(right.is_truthy()))
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => {
+                // Operands evaluate left to right; side effects in the left
+                // operand are observable from the right one.
+                let left_value = self.evaluate_expression(left)?;
+                let right_value = self.evaluate_expression(right)?;
+                self.evaluate_binary(*operator, left_value, right_value, expression.span)
+            }
+            Expression::FunctionCall { name, arguments } => {
+                let mut values = self.evaluate_arguments(arguments)?;
+                // Render the arguments up front: the call consumes them.
+                let traced_arguments = self.trace.then(|| {
+                    values.iter().map(Value::repr).collect::<Vec<_>>().join(", ")
+                });
+                let result = self.call_function(name, &mut values, expression.span);
+                self.recycle_arguments(values);
+                let result = result?;
+                if let Some(rendered) = traced_arguments {
+                    self.trace_lines.push(format!(
+                        "call {}({}) -> {} at {}",
+                        name,
+                        rendered,
+                        result.repr(),
+                        expression.span
+                    ));
+                }
+                Ok(result)
+            }
+            Expression::MethodCall {
+                receiver,
+                name,
+                arguments,
+            } => {
+                // Sugar for `name(receiver, arguments)`: the receiver
+                // evaluates first, then joins the front of the argument list
+                // and dispatches through the ordinary call path.
+                let receiver_value = self.evaluate_expression(receiver)?;
+                let mut values = self.evaluate_arguments(arguments)?;
+                values.insert(0, receiver_value);
+                let traced_arguments = self.trace.then(|| {
+                    values.iter().map(Value::repr).collect::<Vec<_>>().join(", ")
+                });
+                let result = self.call_function(name, &mut values, expression.span);
+                self.recycle_arguments(values);
+                let result = result?;
+                if let Some(rendered) = traced_arguments {
+                    self.trace_lines.push(format!(
+                        "call {}({}) -> {} at {}",
+                        name,
+                        rendered,
+                        result.repr(),
+                        expression.span
+                    ));
+                }
+                Ok(result)
+            }
+            Expression::Lambda { parameters, body } => {
+                // Capture every non-global binding visible at the definition
+                // site, by value; an inner binding shadows an outer one of
+                // the same name. Globals are read live instead.
+                let mut captured: Vec<(String, Value)> = Vec::new();
+                for scope in self.scopes.iter().skip(1) {
+                    for (symbol, value) in &scope.variables {
+                        let name = self.symbols.name(*symbol);
+                        match captured.iter_mut().find(|(existing, _)| existing == name) {
+                            Some((_, slot)) => *slot = value.clone(),
+                            None => captured.push((name.to_string(), value.clone())),
+                        }
+                    }
+                }
+                Ok(Value::Function(Rc::new(FunctionValue {
+                    parameters: parameters.clone(),
+                    body: deep_clone_statements(body),
+                    captured,
+                })))
+            }
+        }
+    }
+
+    fn evaluate_index(
+        &self,
+        target: Value,
+        index: Value,
+        index_span: Span,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        match (target, index) {
+            (Value::Array(elements), Value::Integer(i)) => {
+                let elements = elements.borrow();
+                usize::try_from(i)
+                    .ok()
+                    .and_then(|i| elements.get(i))
+                    .cloned()
+                    .ok_or_else(|| {
+                        RuntimeError::new(
+                            format!("Index {} out of bounds (length {})", i, elements.len()),
+                            span,
+                        )
+                    })
+            }
+            (Value::Map(entries), Value::String(key)) => entries
+                .borrow()
+                .iter()
+                .find(|(existing, _)| *existing == key)
+                .map(|(_, value)| value.clone())
+                .ok_or_else(|| RuntimeError::new(format!("Key not found: {}", key), span)),
+            (Value::String(s), Value::Integer(i)) => usize::try_from(i)
+                .ok()
+                .and_then(|i| s.chars().nth(i))
+                .map(Value::Char)
+                .ok_or_else(|| {
+                    RuntimeError::new(
+                        format!("Index {} out of bounds (length {})", i, s.chars().count()),
+                        span,
+                    )
+                }),
+            (target, index) => Err(RuntimeError::new(
+                format!("Cannot index {:?} with {:?}", target, index),
+                index_span,
+            )),
+        }
+    }
+
+    /// Reject a string result of `length` bytes when it would exceed the
+    /// configured `max_string_len`, before the string is built.
+    fn check_string_len(&self, length: usize, span: Span) -> Result<(), RuntimeError> {
+        match self.max_string_len {
+            Some(limit) if length > limit => Err(RuntimeError::new(
+                format!(
+                    "a string of {} bytes exceeds the {}-byte limit",
+                    length, limit
+                ),
+                span,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Add `bytes` to the allocation count, failing once the total crosses
+    /// the configured `max_heap_bytes`.
+    pub(crate) fn charge_allocation(&mut self, bytes: usize, span: Span) -> Result<(), RuntimeError> {
+        self.allocated_bytes = self.allocated_bytes.saturating_add(bytes);
+        match self.max_heap_bytes {
+            Some(limit) if self.allocated_bytes > limit => Err(RuntimeError::new(
+                format!(
+                    "the program has allocated about {} bytes, over the {}-byte heap limit",
+                    self.allocated_bytes, limit
+                ),
+                span,
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn evaluate_binary(
+        &mut self,
+        operator: BinaryOperator,
+        left: Value,
+        right: Value,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        use BinaryOperator::*;
+        match (operator, left, right) {
+            // Two numbers always go through `numeric_binary`, so the
+            // int/float interplay lives in exactly one place.
+            (
+                operator,
+                left @ (Value::Integer(_) | Value::Float(_)),
+                right @ (Value::Integer(_) | Value::Float(_)),
+            ) => numeric_binary(operator, left, right, span),
+            (Add, Value::String(a), Value::String(b)) => {
+                self.check_string_len(a.len() + b.len(), span)?;
+                self.charge_allocation(a.len() + b.len(), span)?;
+                Ok(Value::String(format!("{}{}", a, b)))
+            }
+            // `"ab" * 3` repeats the string, mirroring `+` for concatenation;
+            // the integer may sit on either side.
+            (Multiply, Value::String(s), Value::Integer(n))
+            | (Multiply, Value::Integer(n), Value::String(s)) => {
+                let count = usize::try_from(n).map_err(|_| {
+                    RuntimeError::new(
+                        format!("cannot repeat a string {} times", n),
+                        span,
+                    )
+                })?;
+                self.check_string_len(s.len().saturating_mul(count), span)?;
+                self.charge_allocation(s.len().saturating_mul(count), span)?;
+                Ok(Value::String(s.repeat(count)))
+            }
+            (Equal, a, b) => Ok(Value::Boolean(a == b)),
+            (NotEqual, a, b) => Ok(Value::Boolean(a != b)),
+            (Less, Value::Char(a), Value::Char(b)) => Ok(Value::Boolean(a < b)),
+            (LessEqual, Value::Char(a), Value::Char(b)) => Ok(Value::Boolean(a <= b)),
+            (Greater, Value::Char(a), Value::Char(b)) => Ok(Value::Boolean(a > b)),
+            (GreaterEqual, Value::Char(a), Value::Char(b)) => Ok(Value::Boolean(a >= b)),
+            (In, Value::Char(needle), Value::String(haystack)) => {
+                Ok(Value::Boolean(haystack.contains(needle)))
+            }
+            (In, needle, Value::Array(elements)) => {
+                Ok(Value::Boolean(elements.borrow().contains(&needle)))
+            }
+            (In, Value::String(key), Value::Map(entries)) => Ok(Value::Boolean(
+                entries.borrow().iter().any(|(existing, _)| *existing == key),
+            )),
+            (In, Value::String(needle), Value::String(haystack)) => {
+                Ok(Value::Boolean(haystack.contains(&needle)))
+            }
+            #[cfg(feature = "bigint")]
+            (operator, left @ Value::BigInteger(_), right)
+            | (operator, left, right @ Value::BigInteger(_)) => {
+                evaluate_big_binary(operator, left, right, span)
+            }
+            // `null` reaches arithmetic most often through an uninitialized
+            // variable; name the types instead of dumping Rust's Debug form.
+            (operator, Value::Null, other) | (operator, other, Value::Null) => {
+                Err(RuntimeError::new(
+                    format!(
+                        "cannot apply `{}` to null and {} — the null may be an uninitialized variable",
+                        operator.symbol(),
+                        other.type_name()
+                    ),
+                    span,
+                ))
+            }
+            (operator, left, right) => Err(RuntimeError::new(
+                format!(
+                    "Unsupported operation: {:?} {} {:?}",
+                    left,
+                    operator.symbol(),
+                    right
+                ),
+                span,
+            )),
+        }
+    }
+
+    /// Evaluate a call's arguments into a vector drawn from the pool; the
+    /// caller hands it back with `recycle_arguments` once the call returns.
+    fn evaluate_arguments(
+        &mut self,
+        arguments: &[Spanned<Expression>],
+    ) -> Result<Vec<Value>, RuntimeError> {
+        let mut values = self.argument_pool.pop().unwrap_or_default();
+        for argument in arguments {
+            match self.evaluate_expression(argument) {
+                Ok(value) => values.push(value),
+                Err(error) => {
+                    self.recycle_arguments(values);
+                    return Err(error);
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    fn recycle_arguments(&mut self, mut buffer: Vec<Value>) {
+        buffer.clear();
+        self.argument_pool.push(buffer);
+    }
+
+    /// Call `name` with `arguments`, draining the vector so its allocation
+    /// can be reused; builtins borrow the values in place instead.
+    pub(crate) fn call_function(
+        &mut self,
+        name: &str,
+        arguments: &mut Vec<Value>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        if let Some(Function::UserDefined { parameters, body }) = self.functions.get(name) {
+            if parameters.len() != arguments.len() {
+                return Err(RuntimeError::new(
+                    format!(
+                        "Function '{}' expected {} arguments, got {}",
+                        name,
+                        parameters.len(),
+                        arguments.len()
+                    ),
+                    span,
+                ));
+            }
+            let parameters = parameters.clone();
+            let body = deep_clone_statements(body);
+            self.call_stack.push(name.to_string());
+            // Scoping is lexical: the body sees its parameters, its own
+            // locals, and globals. The caller's frames are parked for the
+            // duration of the call so the chain is rooted at globals.
+            let caller_frames = self.scopes.split_off(1);
+            // A self tail call comes back as `ControlFlow::TailCall`: rebind
+            // the parameters and rerun the body instead of recursing.
+            let result = loop {
+                self.push_scope();
+                for (parameter, argument) in parameters.iter().zip(arguments.drain(..)) {
+                    // A `_` parameter accepts and ignores its argument.
+                    if parameter == "_" {
+                        continue;
+                    }
+                    let symbol = self.symbols.intern(parameter);
+                    self.scopes
+                        .last_mut()
+                        .expect("there is always at least one scope")
+                        .insert(symbol, argument);
+                }
+                let flow = self.execute_statement_list(&body);
+                self.exit_scope();
+                match flow {
+                    Err(error) => break Err(error),
+                    Ok(ControlFlow::TailCall(next_arguments)) => *arguments = next_arguments,
+                    Ok(ControlFlow::Return(value, _)) => break Ok(value),
+                    Ok(ControlFlow::Normal) => break Ok(Value::Null),
+                    Ok(ControlFlow::Break | ControlFlow::Continue) => {
+                        break Err(RuntimeError::new(
+                            format!("loop control escaped the body of '{}'", name),
+                            span,
+                        ))
+                    }
+                }
+            };
+            self.scopes.truncate(1);
+            self.scopes.extend(caller_frames);
+            self.call_stack.pop();
+            return result;
+        }
+        // A variable holding a function value is callable by name. Only a
+        // function value intercepts the call, so an unrelated variable
+        // sharing a builtin's name doesn't shadow the builtin.
+        let callable = self.symbols.lookup(name).and_then(|symbol| {
+            self.scopes
+                .iter()
+                .rev()
+                .find_map(|scope| scope.get(symbol))
+                .and_then(|value| match value {
+                    Value::Function(function) => Some(Rc::clone(function)),
+                    _ => None,
+                })
+        });
+        if let Some(function) = callable {
+            return self.call_value(&function, std::mem::take(arguments), span);
+        }
+        if let Some(builtin) = self.builtins.get(name) {
+            if let Some((min_args, max_args)) = builtin.arity {
+                if !(min_args..=max_args).contains(&arguments.len()) {
+                    return Err(RuntimeError::new(
+                        format!(
+                            "{}() expects {}, got {}",
+                            name,
+                            describe_arity(min_args, max_args),
+                            arguments.len()
+                        ),
+                        span,
+                    ));
+                }
+            }
+            let function = Rc::clone(&builtin.function);
+            // A panicking builtin must not unwind through the host.
+            // `AssertUnwindSafe` is honest here: the interpreter's own state
+            // is coherent at the call boundary, and any state the builtin
+            // shares with the host is the builtin's to keep unwind-safe (see
+            // `register_builtin`).
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                function(self, arguments, span)
+            }));
+            return result.unwrap_or_else(|payload| {
+                let detail = payload
+                    .downcast_ref::<&str>()
+                    .map(|message| message.to_string())
+                    .or_else(|| payload.downcast_ref::<String>().cloned());
+                Err(RuntimeError::new(
+                    match detail {
+                        Some(detail) => format!("builtin '{}' panicked: {}", name, detail),
+                        None => format!("builtin '{}' panicked", name),
+                    },
+                    span,
+                ))
+            });
+        }
+        Err(RuntimeError::new(
+            format!("Undefined function: {}", name),
+            span,
+        ))
+    }
+
+    /// Call a first-class function value — the path behind lambdas, both when
+    /// called through a variable and when a builtin like `map` invokes one.
+    pub(crate) fn call_value(
+        &mut self,
+        function: &FunctionValue,
+        arguments: Vec<Value>,
+        span: Span,
+    ) -> Result<Value, RuntimeError> {
+        if function.parameters.len() != arguments.len() {
+            return Err(RuntimeError::new(
+                format!(
+                    "Function expected {} arguments, got {}",
+                    function.parameters.len(),
+                    arguments.len()
+                ),
+                span,
+            ));
+        }
+        // A lambda is anonymous, so it has no self tail calls; the `<fn>`
+        // frame keeps a `return f(...)` inside the body from being mistaken
+        // for a tail call of the enclosing named function.
+        self.call_stack.push("<fn>".to_string());
+        // Lexical scoping, as in `call_function`: the caller's frames are
+        // parked, the chain is rooted at globals, and the captured bindings
+        // sit in their own scope under the parameters.
+        let caller_frames = self.scopes.split_off(1);
+        self.push_scope();
+        for (name, value) in &function.captured {
+            let symbol = self.symbols.intern(name);
+            self.scopes
+                .last_mut()
+                .expect("there is always at least one scope")
+                .insert(symbol, value.clone());
+        }
+        self.push_scope();
+        for (parameter, argument) in function.parameters.iter().zip(arguments) {
+            // A `_` parameter accepts and ignores its argument.
+            if parameter == "_" {
+                continue;
+            }
+            let symbol = self.symbols.intern(parameter);
+            self.scopes
+                .last_mut()
+                .expect("there is always at least one scope")
+                .insert(symbol, argument);
+        }
+        let flow = self.execute_statement_list(&function.body);
+        self.scopes.truncate(1);
+        self.scopes.extend(caller_frames);
+        self.call_stack.pop();
+        match flow {
+            Err(error) => Err(error),
+            Ok(ControlFlow::Return(value, _)) => Ok(value),
+            Ok(ControlFlow::Normal) => Ok(Value::Null),
+            Ok(ControlFlow::TailCall(_)) => {
+                unreachable!("a lambda body never produces a tail call")
+            }
+            Ok(ControlFlow::Break | ControlFlow::Continue) => Err(RuntimeError::new(
+                "loop control escaped the body of a function value",
+                span,
+            )),
+        }
+    }
+}
+
+/// Render inclusive arity bounds for an error message: "1 argument",
+/// "2 arguments", "1 or 2 arguments", "1 to 3 arguments".
+fn describe_arity(min_args: usize, max_args: usize) -> String {
+    if min_args == max_args {
+        let plural = if min_args == 1 { "" } else { "s" };
+        format!("{} argument{}", min_args, plural)
+    } else if max_args == min_args + 1 {
+        format!("{} or {} arguments", min_args, max_args)
+    } else {
+        format!("{} to {} arguments", min_args, max_args)
+    }
+}
+
+/// Arithmetic and comparison over the int/float numeric tower. Two integers
+/// stay in integer arithmetic; a float on either side promotes the other
+/// operand, so `int op int` is the only combination that answers with an
+/// integer.
+fn numeric_binary(
+    operator: BinaryOperator,
+    left: Value,
+    right: Value,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    use BinaryOperator::*;
+
+    let unsupported = |left: &Value, right: &Value| {
+        RuntimeError::new(
+            format!(
+                "Unsupported operation: {:?} {} {:?}",
+                left,
+                operator.symbol(),
+                right
+            ),
+            span,
+        )
+    };
+    if let (Value::Integer(a), Value::Integer(b)) = (&left, &right) {
+        let (a, b) = (*a, *b);
+        return match operator {
+            Add => Ok(integer_add(a, b)),
+            Subtract => Ok(integer_subtract(a, b)),
+            Multiply => Ok(integer_multiply(a, b)),
+            Divide if b == 0 => Err(RuntimeError::new("division by zero", span)),
+            Divide => Ok(Value::Integer(a / b)),
+            FloorDivide if b == 0 => Err(RuntimeError::new("floor division by zero", span)),
+            // `div` rounds toward negative infinity, where `/` on integers
+            // truncates toward zero; they differ on negative quotients.
+            FloorDivide => Ok(Value::Integer(a.div_euclid(b))),
+            Modulo if b == 0 => Err(RuntimeError::new("modulo by zero", span)),
+            Modulo => Ok(Value::Integer(a % b)),
+            Less => Ok(Value::Boolean(a < b)),
+            LessEqual => Ok(Value::Boolean(a <= b)),
+            Greater => Ok(Value::Boolean(a > b)),
+            GreaterEqual => Ok(Value::Boolean(a >= b)),
+            Equal => Ok(Value::Boolean(a == b)),
+            NotEqual => Ok(Value::Boolean(a != b)),
+            _ => Err(unsupported(&left, &right)),
+        };
+    }
+    let as_float = |value: &Value| match value {
+        Value::Integer(n) => Some(*n as f64),
+        Value::Float(f) => Some(*f),
+        _ => None,
+    };
+    let (a, b) = match (as_float(&left), as_float(&right)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Err(unsupported(&left, &right)),
+    };
+    // Float arithmetic follows IEEE 754; dividing by zero yields an
+    // infinity rather than an error, matching what floats promise.
+    match operator {
+        Add => Ok(Value::Float(a + b)),
+        Subtract => Ok(Value::Float(a - b)),
+        Multiply => Ok(Value::Float(a * b)),
+        Divide => Ok(Value::Float(a / b)),
+        FloorDivide => Ok(Value::Float((a / b).floor())),
+        Modulo => Ok(Value::Float(a % b)),
+        Less => Ok(Value::Boolean(a < b)),
+        LessEqual => Ok(Value::Boolean(a <= b)),
+        Greater => Ok(Value::Boolean(a > b)),
+        GreaterEqual => Ok(Value::Boolean(a >= b)),
+        Equal => Ok(Value::Boolean(a == b)),
+        NotEqual => Ok(Value::Boolean(a != b)),
+        _ => Err(unsupported(&left, &right)),
+    }
+}
+
+// Integer `+`/`-`/`*`. Without the `bigint` feature these are the plain
+// operators; with it, overflow promotes to an arbitrary-precision integer
+// instead of panicking.
+#[cfg(not(feature = "bigint"))]
+fn integer_add(a: i64, b: i64) -> Value {
+    Value::Integer(a + b)
+}
+
+#[cfg(not(feature = "bigint"))]
+fn integer_subtract(a: i64, b: i64) -> Value {
+    Value::Integer(a - b)
+}
+
+#[cfg(not(feature = "bigint"))]
+fn integer_multiply(a: i64, b: i64) -> Value {
+    Value::Integer(a * b)
+}
+
+#[cfg(feature = "bigint")]
+fn integer_add(a: i64, b: i64) -> Value {
+    match a.checked_add(b) {
+        Some(n) => Value::Integer(n),
+        None => crate::value::integer_from_big(num_bigint::BigInt::from(a) + b),
+    }
+}
+
+#[cfg(feature = "bigint")]
+fn integer_subtract(a: i64, b: i64) -> Value {
+    match a.checked_sub(b) {
+        Some(n) => Value::Integer(n),
+        None => crate::value::integer_from_big(num_bigint::BigInt::from(a) - b),
+    }
+}
+
+#[cfg(feature = "bigint")]
+fn integer_multiply(a: i64, b: i64) -> Value {
+    match a.checked_mul(b) {
+        Some(n) => Value::Integer(n),
+        None => crate::value::integer_from_big(num_bigint::BigInt::from(a) * b),
+    }
+}
+
+/// Arithmetic and comparison once either side has outgrown `i64`. Results
+/// demote back to `Integer` as soon as they fit again.
+#[cfg(feature = "bigint")]
+fn evaluate_big_binary(
+    operator: BinaryOperator,
+    left: Value,
+    right: Value,
+    span: Span,
+) -> Result<Value, RuntimeError> {
+    use crate::value::integer_from_big;
+    use num_bigint::BigInt;
+    use BinaryOperator::*;
+
+    let as_big = |value: &Value| match value {
+        Value::Integer(n) => Some(BigInt::from(*n)),
+        Value::BigInteger(n) => Some(n.clone()),
+        _ => None,
+    };
+    let unsupported = |left: &Value, right: &Value| {
+        RuntimeError::new(
+            format!(
+                "Unsupported operation: {:?} {} {:?}",
+                left,
+                operator.symbol(),
+                right
+            ),
+            span,
+        )
+    };
+    let (a, b) = match (as_big(&left), as_big(&right)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return Err(unsupported(&left, &right)),
+    };
+    let zero = BigInt::from(0);
+    match operator {
+        Add => Ok(integer_from_big(a + b)),
+        Subtract => Ok(integer_from_big(a - b)),
+        Multiply => Ok(integer_from_big(a * b)),
+        Divide if b == zero => Err(RuntimeError::new("division by zero", span)),
+        Divide => Ok(integer_from_big(a / b)),
+        FloorDivide if b == zero => Err(RuntimeError::new("floor division by zero", span)),
+        FloorDivide => {
+            // The Euclidean quotient, matching `div_euclid` on plain
+            // integers.
+            let mut quotient = &a / &b;
+            if &a % &b < zero {
+                if b < zero {
+                    quotient += 1;
+                } else {
+                    quotient -= 1;
+                }
+            }
+            Ok(integer_from_big(quotient))
+        }
+        Modulo if b == zero => Err(RuntimeError::new("modulo by zero", span)),
+        Modulo => Ok(integer_from_big(a % b)),
+        Less => Ok(Value::Boolean(a < b)),
+        LessEqual => Ok(Value::Boolean(a <= b)),
+        Greater => Ok(Value::Boolean(a > b)),
+        GreaterEqual => Ok(Value::Boolean(a >= b)),
+        Equal => Ok(Value::Boolean(a == b)),
+        NotEqual => Ok(Value::Boolean(a != b)),
+        _ => Err(unsupported(&left, &right)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_parser::parse_program;
+
+    fn run(source: &str) -> Result<Vec<String>, RuntimeError> {
+        let program = parse_program(source).expect("test programs should parse");
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program)?;
+        Ok(interpreter.output_lines().to_vec())
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(run("print(1 + 2 * 3);").unwrap(), vec!["7"]);
+    }
+
+    #[test]
+    fn variables_and_reassignment() {
+        assert_eq!(run("x = 1; x = x + 1; print(x);").unwrap(), vec!["2"]);
+    }
+
+    #[test]
+    fn while_loop() {
+        let source = "total = 0; i = 1; while (i <= 5) { total = total + i; i = i + 1; } print(total);";
+        assert_eq!(run(source).unwrap(), vec!["15"]);
+    }
+
+    #[test]
+    fn while_else_runs_when_the_loop_never_executes() {
+        let source = "while (false) { print(\"body\"); } else { print(\"empty\"); }";
+        assert_eq!(run(source).unwrap(), vec!["empty"]);
+    }
+
+    #[test]
+    fn while_else_is_skipped_after_any_iteration() {
+        let source =
+            "i = 0; while (i < 2) { print(i); i = i + 1; } else { print(\"empty\"); }";
+        assert_eq!(run(source).unwrap(), vec!["0", "1"]);
+    }
+
+    #[test]
+    fn for_in_iterates_array_elements() {
+        let source = "total = 0; for x in [1, 2, 3] { total = total + x; } print(total);";
+        assert_eq!(run(source).unwrap(), vec!["6"]);
+    }
+
+    #[test]
+    fn for_in_iterates_string_characters() {
+        assert_eq!(
+            run("for c in \"ab\" { print(c); }").unwrap(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn for_in_iterates_map_keys() {
+        assert_eq!(
+            run("for k in {a: 1, b: 2} { print(k); }").unwrap(),
+            vec!["a", "b"]
+        );
+    }
+
+    #[test]
+    fn for_in_honors_break_and_continue() {
+        let source = "for x in [1, 2, 3, 4] { if (x == 2) { continue; } if (x == 4) { break; } print(x); }";
+        assert_eq!(run(source).unwrap(), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn the_loop_variable_does_not_leak_out_of_the_loop() {
+        let error = run("for x in [1] { } print(x);").unwrap_err();
+        assert_eq!(error.message, "Undefined variable: x");
+    }
+
+    #[test]
+    fn iterating_a_non_iterable_is_a_spanned_error() {
+        let error = run("for x in 5 { }").unwrap_err();
+        assert_eq!(error.message, "Cannot iterate over 5");
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn function_call_and_return() {
+        let source = "def add(a, b) { return a + b; } print(add(2, 3));";
+        assert_eq!(run(source).unwrap(), vec!["5"]);
+    }
+
+    #[test]
+    fn if_else() {
+        assert_eq!(
+            run("if (1 < 2) { print(\"yes\"); } else { print(\"no\"); }").unwrap(),
+            vec!["yes"]
+        );
+    }
+
+    #[test]
+    fn string_concatenation() {
+        assert_eq!(run("print(\"foo\" + \"bar\");").unwrap(), vec!["foobar"]);
+    }
+
+    #[test]
+    fn string_repetition() {
+        assert_eq!(run(r#"print("-" * 10);"#).unwrap(), vec!["----------"]);
+        assert_eq!(run(r#"print(3 * "ab");"#).unwrap(), vec!["ababab"]);
+    }
+
+    #[test]
+    fn string_repetition_by_zero_is_empty() {
+        assert_eq!(run(r#"print("ab" * 0, "end");"#).unwrap(), vec![" end"]);
+    }
+
+    #[test]
+    fn string_repetition_by_a_negative_count_is_a_spanned_error() {
+        let error = run(r#"x = "ab" * (0 - 2);"#).unwrap_err();
+        assert_eq!(error.message, "cannot repeat a string -2 times");
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn break_and_continue() {
+        let source = "i = 0; while (true) { i = i + 1; if (i == 2) { continue; } if (i > 3) { break; } print(i); }";
+        assert_eq!(run(source).unwrap(), vec!["1", "3"]);
+    }
+
+    #[test]
+    fn membership_in_array() {
+        assert_eq!(
+            run("items = [1, 2, 3]; print(2 in items, 5 in items);").unwrap(),
+            vec!["true false"]
+        );
+    }
+
+    #[test]
+    fn membership_in_map_checks_keys() {
+        let source = r#"m = {host: "localhost", port: 8080}; print("host" in m, "localhost" in m);"#;
+        assert_eq!(run(source).unwrap(), vec!["true false"]);
+    }
+
+    #[test]
+    fn membership_in_string_is_substring() {
+        assert_eq!(
+            run(r#"print("mar" in "amarok", "rok " in "amarok");"#).unwrap(),
+            vec!["true false"]
+        );
+    }
+
+    #[test]
+    fn compound_assignment_runs_as_its_expansion() {
+        assert_eq!(run("x = 1; x += 2; x *= 3; print(x);").unwrap(), vec!["9"]);
+    }
+
+    #[test]
+    fn match_dispatches_on_integers_and_strings() {
+        assert_eq!(
+            run("x = 2; match x { 1 => { print(\"one\"); } 2 => { print(\"two\"); } _ => { print(\"other\"); } }")
+                .unwrap(),
+            vec!["two"]
+        );
+        assert_eq!(
+            run("s = \"b\"; match s { \"a\" => { print(1); } \"b\" => { print(2); } }").unwrap(),
+            vec!["2"]
+        );
+    }
+
+    #[test]
+    fn match_falls_to_the_default_arm() {
+        assert_eq!(
+            run("match 9 { 1 => { print(\"one\"); } _ => { print(\"other\"); } }").unwrap(),
+            vec!["other"]
+        );
+    }
+
+    #[test]
+    fn a_match_with_no_matching_arm_and_no_default_is_a_no_op() {
+        assert_eq!(
+            run("match 9 { 1 => { print(1); } } print(\"after\");").unwrap(),
+            vec!["after"]
+        );
+    }
+
+    #[test]
+    fn max_string_len_stops_a_doubling_loop() {
+        let program =
+            amarok_parser::parse_program("s = \"xxxx\"; while (true) { s = s + s; }").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_string_len(Some(1024));
+        let error = interpreter.run_program(&program).unwrap_err();
+        assert_eq!(
+            error.message,
+            "a string of 2048 bytes exceeds the 1024-byte limit"
+        );
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn oversized_repetition_fails_before_allocating() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_string_len(Some(16));
+        let program = amarok_parser::parse_program("s = \"ab\" * 1000000000000;").unwrap();
+        let error = interpreter.run_program(&program).unwrap_err();
+        assert!(error.message.contains("exceeds the 16-byte limit"));
+    }
+
+    #[test]
+    fn string_building_is_unlimited_by_default() {
+        assert_eq!(run("print(len(\"ab\" * 100000));").unwrap(), vec!["200000"]);
+    }
+
+    #[test]
+    fn deep_block_nesting_hits_the_scope_limit_gracefully() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_scope_depth(8);
+        let source = format!("{}print(1);{}", "{".repeat(20), "}".repeat(20));
+        let program = amarok_parser::parse_program(&source).unwrap();
+        let error = interpreter.run_program(&program).unwrap_err();
+        assert_eq!(
+            error.message,
+            "blocks nested deeper than the scope limit (8)"
+        );
+        // The interpreter stays usable after refusing.
+        let program = amarok_parser::parse_program("print(2);").unwrap();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), vec!["2"]);
+    }
+
+    #[test]
+    fn ordinary_nesting_is_nowhere_near_the_default_limit() {
+        // 30 blocks is as deep as the parser's own bracket cap allows, and
+        // still comfortably under the default scope limit.
+        let source = format!("{}print(1);{}", "{".repeat(30), "}".repeat(30));
+        assert_eq!(run(&source).unwrap(), vec!["1"]);
+    }
+
+    #[test]
+    fn calls_reset_the_depth_rather_than_inheriting_the_callers() {
+        // Each call parks the caller's frames, so nesting at the call site
+        // does not eat into the callee's budget.
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_scope_depth(8);
+        let source = format!(
+            "def f() {{ return 7; }} {}print(f());{}",
+            "{".repeat(6),
+            "}".repeat(6)
+        );
+        let program = amarok_parser::parse_program(&source).unwrap();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), vec!["7"]);
+    }
+
+    #[test]
+    fn a_large_array_under_a_small_heap_cap_errors_cleanly() {
+        let mut interpreter = Interpreter::new();
+        interpreter.set_max_heap_bytes(Some(1024));
+        let program = parse_program("x = range(1000000);").unwrap();
+        let error = interpreter.run_program(&program).unwrap_err();
+        assert!(
+            error.message.contains("over the 1024-byte heap limit"),
+            "message was: {}",
+            error.message
+        );
+        // The interpreter stays usable after refusing.
+        let program = parse_program("print(1);").unwrap();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), vec!["1"]);
+    }
+
+    #[test]
+    fn allocation_is_unlimited_by_default_but_still_counted() {
+        let mut interpreter = Interpreter::new();
+        let program = parse_program("x = range(1000); s = \"ab\" * 100;").unwrap();
+        interpreter.run_program(&program).unwrap();
+        assert!(interpreter.allocated_bytes() >= 1000 * std::mem::size_of::<Value>() + 200);
+    }
+
+    #[test]
+    fn vars_lists_names_in_definition_order() {
+        assert_eq!(
+            run("a = 1; b = 2; x = vars(); print(x[0], x[1]);").unwrap(),
+            vec!["a b"]
+        );
+    }
+
+    #[test]
+    fn vars_follows_the_scope_chain_and_has_var_tests_membership() {
+        let source = "a = 1; { b = 2; print(\"b\" in vars()); } \
+                      print(\"b\" in vars(), has_var(\"a\"), has_var(\"b\"));";
+        assert_eq!(run(source).unwrap(), vec!["true", "false true false"]);
+    }
+
+    #[test]
+    fn run_statement_keeps_state_and_echoes_expression_values() {
+        let program = amarok_parser::parse_program("x = 2; x * 3;").unwrap();
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.run_statement(&program.statements[0]).unwrap(),
+            None
+        );
+        assert_eq!(
+            interpreter.run_statement(&program.statements[1]).unwrap(),
+            Some(Value::Integer(6))
+        );
+    }
+
+    #[test]
+    fn a_top_level_return_through_run_statement_is_an_error() {
+        let program = amarok_parser::parse_program("return 1;").unwrap();
+        let mut interpreter = Interpreter::new();
+        let error = interpreter
+            .run_statement(&program.statements[0])
+            .unwrap_err();
+        assert_eq!(error.message, "Return outside of function");
+    }
+
+    #[test]
+    fn grouped_number_format_adds_thousands_separators_to_print() {
+        let program =
+            amarok_parser::parse_program("print(1000000); print(1234.5); print(0 - 4321);")
+                .unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_number_format(NumberFormat::Grouped);
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), ["1,000,000", "1,234.5", "-4,321"]);
+    }
+
+    #[test]
+    fn number_formatting_defaults_to_plain() {
+        assert_eq!(run("print(1000000);").unwrap(), vec!["1000000"]);
+    }
+
+    #[test]
+    fn an_error_inside_a_compound_assignment_names_the_original() {
+        let error = run("x = null; x += 1;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "cannot apply `+` to null and int — the null may be an uninitialized variable \
+             (in expansion of `x += 1`)"
+        );
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn arithmetic_on_null_names_the_types() {
+        let error = run("x = null + 1;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "cannot apply `+` to null and int — the null may be an uninitialized variable"
+        );
+        assert!(!error.message.contains("Integer("));
+        assert_eq!(error.span, Some(Span::new(4, 12)));
+    }
+
+    #[test]
+    fn membership_type_mismatch_is_a_spanned_error() {
+        let error = run("x = 1 in 5;").unwrap_err();
+        assert_eq!(error.message, "Unsupported operation: Integer(1) in Integer(5)");
+        assert_eq!(error.span, Some(Span::new(4, 10)));
+    }
+
+    #[test]
+    fn array_and_map_indexing() {
+        let source = r#"items = [10, 20]; m = {a: 1}; print(items[1], m["a"]);"#;
+        assert_eq!(run(source).unwrap(), vec!["20 1"]);
+    }
+
+    #[test]
+    fn shadowing_a_builtin_warns_but_still_runs() {
+        let program =
+            parse_program("def len(x) { return 99; } print(len(\"abc\"));").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), ["99"]);
+        assert_eq!(interpreter.warnings().len(), 1);
+        assert_eq!(
+            interpreter.warnings()[0].message,
+            "function 'len' shadows the builtin of the same name"
+        );
+    }
+
+    #[test]
+    fn mixed_numeric_equality_widens() {
+        let program = parse_program("print(1 == 1.0, 1 == 1.5);").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), ["true false"]);
+        assert!(interpreter.warnings().is_empty());
+    }
+
+    #[test]
+    fn the_numeric_tower_widens_and_narrows_per_operator() {
+        // Every arithmetic and comparison operator, over each int/float
+        // pairing: int op int stays an integer, any float widens the result.
+        let cases = [
+            ("+", ["7", "7.5", "6.5", "8.25"]),
+            ("-", ["3", "2.5", "2.5", "2.75"]),
+            ("*", ["10", "12.5", "9.0", "15.125"]),
+            ("/", ["2", "2.0", "2.25", "2.0"]),
+            ("div", ["2", "2.0", "2.0", "2.0"]),
+            ("%", ["1", "0.0", "0.5", "0.0"]),
+            ("<", ["false", "false", "false", "false"]),
+            ("<=", ["false", "false", "false", "false"]),
+            (">", ["true", "true", "true", "true"]),
+            (">=", ["true", "true", "true", "true"]),
+            ("==", ["false", "false", "false", "false"]),
+            ("!=", ["true", "true", "true", "true"]),
+        ];
+        for (operator, expected) in cases {
+            let source = format!(
+                "print(5 {op} 2); print(5 {op} 2.5); print(4.5 {op} 2); print(5.5 {op} 2.75);",
+                op = operator
+            );
+            assert_eq!(run(&source).unwrap(), expected, "operator {}", operator);
+        }
+    }
+
+    #[test]
+    fn let_with_a_matching_annotation_binds() {
+        assert_eq!(
+            run("let x: int = 5; let s: string = \"hi\"; print(x, s);").unwrap(),
+            vec!["5 hi"]
+        );
+    }
+
+    #[test]
+    fn let_with_a_mismatching_annotation_is_a_spanned_error() {
+        let error = run("let x: int = 1.5;").unwrap_err();
+        assert_eq!(error.message, "`x` is declared int but its value is float");
+        assert_eq!(error.span, Some(Span::new(0, 17)));
+    }
+
+    #[test]
+    fn let_without_an_annotation_just_binds() {
+        assert_eq!(run("let x = 1 + 2; print(x);").unwrap(), vec!["3"]);
+    }
+
+    #[test]
+    fn let_shadows_only_inside_its_scope() {
+        let source = "x = 1; { let x: int = 2; print(x); } print(x);";
+        assert_eq!(run(source).unwrap(), vec!["2", "1"]);
+    }
+
+    #[test]
+    fn underscore_discards_assignments() {
+        assert_eq!(run("_ = 1 + 2; print(\"ok\");").unwrap(), vec!["ok"]);
+    }
+
+    #[test]
+    fn underscore_parameters_ignore_their_argument() {
+        let source = "def second(_, x) { return x; } print(second(1, 2));";
+        assert_eq!(run(source).unwrap(), vec!["2"]);
+    }
+
+    #[test]
+    fn reading_underscore_is_an_error() {
+        let error = run("_ = 1; print(_);").unwrap_err();
+        assert_eq!(error.message, "cannot read the discard pattern `_`");
+    }
+
+    #[test]
+    fn runtime_error_boxes_into_dyn_error() {
+        let error = run("print(missing);").unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert_eq!(boxed.to_string(), "Undefined variable: missing at 6..13");
+    }
+
+    #[test]
+    fn functions_cannot_read_caller_locals() {
+        let source = "
+            def outer() {
+                secret = 1;
+                return inner();
+            }
+            def inner() {
+                return secret;
+            }
+            outer();
+        ";
+        let error = run(source).unwrap_err();
+        assert_eq!(error.message, "Undefined variable: secret");
+    }
+
+    #[test]
+    fn functions_still_read_globals() {
+        let source = "
+            base = 10;
+            def bump(n) {
+                return base + n;
+            }
+            print(bump(5));
+        ";
+        assert_eq!(run(source).unwrap(), vec!["15"]);
+    }
+
+    #[test]
+    fn tail_recursion_runs_in_constant_stack() {
+        // Deep enough to overflow the stack if each call recursed through
+        // call_function.
+        let source = r#"
+            def countdown(n) {
+                if (n == 0) {
+                    return "done";
+                }
+                return countdown(n - 1);
+            }
+            print(countdown(200000));
+        "#;
+        assert_eq!(run(source).unwrap(), vec!["done"]);
+    }
+
+    #[test]
+    fn non_tail_recursion_still_accumulates() {
+        let source = "
+            def sum(n) {
+                if (n == 0) {
+                    return 0;
+                }
+                return n + sum(n - 1);
+            }
+            print(sum(10));
+        ";
+        assert_eq!(run(source).unwrap(), vec!["55"]);
+    }
+
+    #[test]
+    fn lambdas_call_through_a_variable() {
+        let source = "double = fn(x) { return x * 2; }; print(double(5));";
+        assert_eq!(run(source).unwrap(), vec!["10"]);
+    }
+
+    #[test]
+    fn lambdas_capture_their_environment() {
+        let source = "
+            def make_adder(n) {
+                return fn(x) { return x + n; };
+            }
+            add2 = make_adder(2);
+            print(add2(40));
+        ";
+        assert_eq!(run(source).unwrap(), vec!["42"]);
+    }
+
+    #[test]
+    fn lambdas_print_their_signature() {
+        assert_eq!(run("f = fn(a, b) {}; print(f);").unwrap(), vec!["<fn(a, b)>"]);
+    }
+
+    #[test]
+    fn lambda_arity_mismatch_is_a_spanned_error() {
+        let error = run("f = fn(x) { return x; }; f(1, 2);").unwrap_err();
+        assert_eq!(error.message, "Function expected 1 arguments, got 2");
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn top_level_return_error_points_at_the_return() {
+        let error = run("x = 1; return x;").unwrap_err();
+        assert_eq!(error.message, "Return outside of function");
+        assert_eq!(error.span, Some(Span::new(7, 16)));
+    }
+
+    #[test]
+    fn return_inside_a_called_loop_does_not_leak_into_the_caller() {
+        // `step` returns from inside its own loop; the Return must be consumed
+        // by the call, not treated as control flow of the outer while.
+        let source = "
+            count = 0;
+            def step() {
+                while (true) {
+                    return count < 3;
+                }
+            }
+            while (step()) {
+                count = count + 1;
+            }
+            print(count);
+        ";
+        assert_eq!(run(source).unwrap(), vec!["3"]);
+    }
+
+    #[test]
+    fn host_defined_functions_are_callable_from_scripts() {
+        // The body is ordinary parsed AST; hosts can equally build it by hand.
+        let body = parse_program("return a + b;").unwrap().statements;
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .define_function("add", vec!["a".to_string(), "b".to_string()], body)
+            .unwrap();
+        let program = parse_program("print(add(2, 3));").unwrap();
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), ["5"]);
+    }
+
+    #[test]
+    fn define_function_rejects_duplicate_parameters() {
+        let mut interpreter = Interpreter::new();
+        let error = interpreter
+            .define_function("f", vec!["a".to_string(), "a".to_string()], Vec::new())
+            .unwrap_err();
+        assert_eq!(error.message, "duplicate parameter `a` in function 'f'");
+    }
+
+    #[test]
+    fn define_function_allows_repeated_discards() {
+        let mut interpreter = Interpreter::new();
+        interpreter
+            .define_function("f", vec!["_".to_string(), "_".to_string()], Vec::new())
+            .unwrap();
+    }
+
+    #[test]
+    fn host_injected_globals_read_like_variables() {
+        let source = r#"
+            print(VERSION);
+            def show(VERSION) { print(VERSION); }
+            show("shadowed");
+            print(VERSION);
+        "#;
+        let program = parse_program(source).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_global("VERSION", Value::String("1.0".to_string()));
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(interpreter.output_lines(), ["1.0", "shadowed", "1.0"]);
+    }
+
+    #[test]
+    fn empty_bodied_function_returns_null() {
+        let output = run("def f() {} print(f() == null);").unwrap();
+        assert_eq!(output, vec!["true"]);
+    }
+
+    #[test]
+    fn binary_operands_evaluate_left_to_right() {
+        use std::cell::Cell;
+
+        let program = parse_program("print(next() - next());").unwrap();
+        let mut interpreter = Interpreter::new();
+        let counter = Rc::new(Cell::new(0));
+        let counter_in_builtin = Rc::clone(&counter);
+        interpreter.register_builtin("next", move |_interpreter, _arguments, _span| {
+            counter_in_builtin.set(counter_in_builtin.get() + 1);
+            Ok(Value::Integer(counter_in_builtin.get()))
+        });
+        interpreter.run_program(&program).unwrap();
+        // 1 - 2: the left operand drew from the counter first.
+        assert_eq!(interpreter.output_lines(), ["-1"]);
+        assert_eq!(counter.get(), 2);
+    }
+
+    #[test]
+    fn a_panicking_builtin_becomes_a_runtime_error() {
+        let program = parse_program("boom();").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.register_builtin("boom", |_interpreter, _arguments, _span| {
+            panic!("deliberate");
+        });
+        // Silence the default panic hook for the expected panic, then put it
+        // back so other tests still report theirs.
+        let previous_hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let result = interpreter.run_program(&program);
+        std::panic::set_hook(previous_hook);
+        let error = result.unwrap_err();
+        assert_eq!(error.message, "builtin 'boom' panicked: deliberate");
+        assert!(error.span.is_some());
+        // The interpreter survives and keeps running.
+        let follow_up = parse_program("print(1);").unwrap();
+        interpreter.run_program(&follow_up).unwrap();
+        assert_eq!(interpreter.output_lines(), ["1"]);
+    }
+
+    #[test]
+    fn method_calls_desugar_to_builtin_calls() {
+        assert_eq!(run("print(\"abc\".len());").unwrap(), vec!["3"]);
+    }
+
+    #[test]
+    fn method_calls_reach_user_functions_too() {
+        let source = "def double(x) { return x * 2; } print(4.double());";
+        assert_eq!(run(source).unwrap(), vec!["8"]);
+    }
+
+    #[test]
+    fn an_unknown_method_is_an_undefined_function() {
+        let error = run("5.frobnicate();").unwrap_err();
+        assert_eq!(error.message, "Undefined function: frobnicate");
+    }
+
+    #[test]
+    fn a_tight_loop_interns_each_name_once() {
+        let program = parse_program(
+            "i = 0; total = 0; while (i < 1000) { total = total + i; i = i + 1; }",
+        )
+        .unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        // Two variables, two symbols: the thousands of accesses in the loop
+        // all resolved through the same interned ids.
+        assert_eq!(interpreter.symbols.len(), 2);
+    }
+
+    /// Not a correctness test: a timing probe for the symbol-keyed scope
+    /// lookup. Run with `cargo test -- --ignored --nocapture` to measure.
+    #[test]
+    #[ignore = "timing probe, run manually"]
+    fn bench_variable_access_in_a_tight_loop() {
+        let program = parse_program(
+            "i = 0; total = 0; while (i < 1000000) { total = total + i; i = i + 1; }",
+        )
+        .unwrap();
+        let mut interpreter = Interpreter::new();
+        let start = std::time::Instant::now();
+        interpreter.run_program(&program).unwrap();
+        eprintln!("1M-iteration loop: {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn repeated_calls_reuse_the_argument_buffer() {
+        use std::cell::RefCell;
+
+        let program = parse_program(
+            "i = 0; while (i < 100) { observe(i, i + 1); i = i + 1; }",
+        )
+        .unwrap();
+        let mut interpreter = Interpreter::new();
+        let pointers = Rc::new(RefCell::new(Vec::new()));
+        let pointers_in_builtin = Rc::clone(&pointers);
+        interpreter.register_builtin("observe", move |_interpreter, arguments, _span| {
+            pointers_in_builtin.borrow_mut().push(arguments.as_ptr());
+            Ok(Value::Null)
+        });
+        interpreter.run_program(&program).unwrap();
+        let pointers = pointers.borrow();
+        assert_eq!(pointers.len(), 100);
+        // The first call allocates the buffer; every later call pops it back
+        // out of the pool instead of allocating a fresh vector.
+        assert!(pointers.iter().all(|pointer| *pointer == pointers[0]));
+    }
+
+    #[test]
+    fn char_literals_evaluate_and_print_bare() {
+        let source = r"print('a', '\n' == '\n', 'a' < 'b');";
+        assert_eq!(run(source).unwrap(), vec!["a true true"]);
+    }
+
+    #[test]
+    fn string_indexing_yields_a_char() {
+        let source = r#"s = "hey"; print(s[1], s[1] == 'e');"#;
+        assert_eq!(run(source).unwrap(), vec!["e true"]);
+    }
+
+    #[test]
+    fn index_assignment_updates_arrays_and_maps() {
+        let source = r#"
+            items = [1, 2, 3];
+            items[1] = 20;
+            m = {a: 1};
+            m["a"] = 10;
+            m["b"] = 2;
+            print(items, m);
+        "#;
+        assert_eq!(run(source).unwrap(), vec!["[1, 20, 3] {a: 10, b: 2}"]);
+    }
+
+    #[test]
+    fn nested_index_assignment() {
+        let source = "grid = [[1, 2], [3, 4]]; grid[1][0] = 30; print(grid);";
+        assert_eq!(run(source).unwrap(), vec!["[[1, 2], [30, 4]]"]);
+    }
+
+    #[test]
+    fn index_assignment_out_of_bounds_error() {
+        let error = run("items = [1]; items[5] = 0;").unwrap_err();
+        assert_eq!(error.message, "Index 5 out of bounds (length 1)");
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn index_assignment_to_a_non_collection_error() {
+        let error = run("x = 1; x[0] = 2;").unwrap_err();
+        assert!(error.message.starts_with("Cannot index-assign"));
+    }
+
+    #[test]
+    fn index_out_of_bounds_error() {
+        let error = run("items = [1]; items[3];").unwrap_err();
+        assert_eq!(error.message, "Index 3 out of bounds (length 1)");
+    }
+
+    #[test]
+    fn undefined_variable_error() {
+        let error = run("print(missing);").unwrap_err();
+        assert_eq!(error.message, "Undefined variable: missing");
+        assert_eq!(error.span, Some(Span::new(6, 13)));
+    }
+
+    #[test]
+    fn division_by_zero_error() {
+        let error = run("x = 1 / 0;").unwrap_err();
+        assert_eq!(error.message, "division by zero");
+    }
+
+    #[test]
+    fn floor_division_on_positive_integers_matches_slash() {
+        assert_eq!(run("print(7 / 2, 7 div 2);").unwrap(), vec!["3 3"]);
+    }
+
+    #[test]
+    fn floor_division_rounds_toward_negative_infinity() {
+        // `/` truncates toward zero; `div` floors.
+        assert_eq!(
+            run("print((0 - 7) / 2, (0 - 7) div 2);").unwrap(),
+            vec!["-3 -4"]
+        );
+    }
+
+    #[test]
+    fn floor_division_on_floats_floors_the_quotient() {
+        assert_eq!(run("print(7.0 div 2.0);").unwrap(), vec!["3.0"]);
+    }
+
+    #[test]
+    fn floor_division_by_zero_error() {
+        let error = run("x = 1 div 0;").unwrap_err();
+        assert_eq!(error.message, "floor division by zero");
+        assert!(error.span.is_some());
+    }
+
+    #[test]
+    fn scope_enumeration_is_in_definition_order() {
+        let program =
+            parse_program("delta = 1; alpha = 2; echo = 3; bravo = 4; zulu = 5; charlie = 6;")
+                .unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        let names: Vec<String> = interpreter
+            .debug_dump_scope()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, ["delta", "alpha", "echo", "bravo", "zulu", "charlie"]);
+    }
+
+    #[test]
+    fn trace_records_assignments_and_calls_in_order() {
+        let source = "def add(a, b) { return a + b; } x = 3; y = add(2, x);";
+        let program = parse_program(source).unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.set_trace(true);
+        interpreter.run_program(&program).unwrap();
+        assert_eq!(
+            interpreter.trace_lines(),
+            [
+                "assign x = 3 at 32..38",
+                "call add(2, 3) -> 5 at 43..52",
+                "assign y = 5 at 39..53",
+            ]
+        );
+    }
+
+    #[test]
+    fn trace_is_off_by_default() {
+        let program = parse_program("x = 1;").unwrap();
+        let mut interpreter = Interpreter::new();
+        interpreter.run_program(&program).unwrap();
+        assert!(interpreter.trace_lines().is_empty());
+    }
+
+    #[test]
+    fn statement_hook_observes_each_statement_in_order() {
+        use std::cell::RefCell;
+
+        let program = parse_program("x = 1; if (x) { y = 2; }").unwrap();
+        let starts = Rc::new(RefCell::new(Vec::new()));
+        let mut interpreter = Interpreter::new();
+        let recorded = Rc::clone(&starts);
+        interpreter.set_statement_hook(move |statement, _| {
+            recorded.borrow_mut().push(statement.span.start);
+        });
+        interpreter.run_program(&program).unwrap();
+        // The assignment, the `if`, then the statement inside its block.
+        assert_eq!(*starts.borrow(), vec![0, 7, 16]);
+    }
+
+    #[test]
+    fn the_interrupt_flag_stops_a_running_loop() {
+        let program = parse_program("i = 0; while (true) { i = i + 1; }").unwrap();
+        let mut interpreter = Interpreter::new();
+        let flag = interpreter.interrupt_flag();
+        let mut statements_seen = 0;
+        interpreter.set_statement_hook(move |_, _| {
+            statements_seen += 1;
+            if statements_seen == 10 {
+                flag.store(true, Ordering::Relaxed);
+            }
+        });
+        let error = interpreter.run_program(&program).unwrap_err();
+        assert_eq!(error.message, "interrupted");
+        assert!(error.span.is_some());
+
+        // The flag cleared when it fired, so the session keeps working.
+        interpreter.clear_statement_hook();
+        let follow_up = parse_program("print(1 + 1);").unwrap();
+        interpreter.run_program(&follow_up).unwrap();
+        assert_eq!(interpreter.output_lines(), ["2"]);
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn factorial_of_50_does_not_overflow() {
+        let source =
+            "def fact(n) { if (n == 0) { return 1; } return n * fact(n - 1); } print(fact(50));";
+        assert_eq!(
+            run(source).unwrap(),
+            vec!["30414093201713378043612608166064768844377641568960512000000000000"]
+        );
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_results_demote_once_they_fit_again() {
+        let source = "def fact(n) { if (n == 0) { return 1; } return n * fact(n - 1); } \
+                      print(fact(50) / fact(49), fact(50) > fact(49));";
+        assert_eq!(run(source).unwrap(), vec!["50 true"]);
+    }
+
+    #[test]
+    fn eval_program_returns_last_expression_value() {
+        let program = parse_program("a = 2; a + 3;").unwrap();
+        let mut interpreter = Interpreter::new();
+        assert_eq!(
+            interpreter.eval_program(&program).unwrap(),
+            Some(Value::Integer(5))
+        );
+    }
+}
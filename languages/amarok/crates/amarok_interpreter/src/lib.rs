@@ -0,0 +1,13 @@
+//! A tree-walking interpreter for the Amarok language.
+
+mod builtins;
+mod error;
+mod interpreter;
+mod lint;
+mod symbol;
+mod value;
+
+pub use error::RuntimeError;
+pub use lint::lint_program;
+pub use interpreter::{BuiltinFunction, Interpreter, StatementHook, DEFAULT_MAX_SCOPE_DEPTH};
+pub use value::{format_value, format_value_with, FunctionValue, NumberFormat, Value};
@@ -0,0 +1,174 @@
+//! Static lints over a parsed program, run before execution when the host
+//! opts in with `Interpreter::set_lint`.
+
+use amarok_syntax::ast::{Expression, Program, Statement};
+use amarok_syntax::Spanned;
+
+use crate::error::RuntimeError;
+
+/// Walk `program` and report:
+///
+/// - every assignment in a nested block that targets a parameter of the
+///   enclosing function. Reassigning a parameter at the top of the body is
+///   idiomatic; doing it inside a nested block reads like a local and
+///   shadows the parameter when written with `let`, so both forms get a
+///   warning pointing at the assignment and at the definition;
+/// - every statement that computes a pure expression — no calls anywhere
+///   inside — and discards the result, which makes the statement a no-op.
+pub fn lint_program(program: &Program) -> Vec<RuntimeError> {
+    let mut warnings = Vec::new();
+    lint_statements(&program.statements, None, 0, &mut warnings);
+    warnings
+}
+
+/// `function` is the enclosing definition (name, parameters, span rendered
+/// into the message) and `depth` counts blocks below its body: the body
+/// itself is depth 0, so only genuinely nested assignments fire.
+fn lint_statements(
+    statements: &[Spanned<Statement>],
+    function: Option<(&str, &[String], amarok_syntax::Span)>,
+    depth: usize,
+    warnings: &mut Vec<RuntimeError>,
+) {
+    for statement in statements {
+        match &statement.value {
+            Statement::FunctionDefinition {
+                name,
+                parameters,
+                body,
+                ..
+            } => {
+                lint_statements(body, Some((name, parameters, statement.span)), 0, warnings);
+            }
+            Statement::Assignment { name, .. } | Statement::Let { name, .. } => {
+                if depth == 0 {
+                    continue;
+                }
+                let Some((function_name, parameters, definition_span)) = function else {
+                    continue;
+                };
+                if parameters.iter().any(|parameter| parameter == name) {
+                    warnings.push(RuntimeError::new(
+                        format!(
+                            "assignment to `{}` in a nested block shadows a parameter of '{}' (defined at {})",
+                            name, function_name, definition_span
+                        ),
+                        statement.span,
+                    ));
+                }
+            }
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                lint_statements(then_branch, function, depth + 1, warnings);
+                if let Some(else_branch) = else_branch {
+                    lint_statements(else_branch, function, depth + 1, warnings);
+                }
+            }
+            Statement::While {
+                body, else_branch, ..
+            } => {
+                lint_statements(body, function, depth + 1, warnings);
+                if let Some(else_branch) = else_branch {
+                    lint_statements(else_branch, function, depth + 1, warnings);
+                }
+            }
+            Statement::ForIn { body, .. } => {
+                lint_statements(body, function, depth + 1, warnings);
+            }
+            Statement::Match { arms, .. } => {
+                for arm in arms {
+                    lint_statements(&arm.body, function, depth + 1, warnings);
+                }
+            }
+            Statement::Desugared { statement, .. } => {
+                lint_statements(std::slice::from_ref(&**statement), function, depth, warnings);
+            }
+            Statement::Expression(expression) if expression_is_pure(&expression.value) => {
+                warnings.push(RuntimeError::new(
+                    "this expression's result is discarded; assign it or remove the statement",
+                    statement.span,
+                ));
+            }
+            Statement::Block(body) => {
+                lint_statements(body, function, depth + 1, warnings);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Pure means no calls can run while evaluating it: literals, variable
+/// reads, and operators over those. A call in any position may have side
+/// effects, so the statement around it is left alone.
+fn expression_is_pure(expression: &Expression) -> bool {
+    match expression {
+        Expression::Null
+        | Expression::Integer(_)
+        | Expression::Float(_)
+        | Expression::Boolean(_)
+        | Expression::Char(_)
+        | Expression::String(_)
+        | Expression::Variable(_) => true,
+        Expression::Unary { operand, .. } => expression_is_pure(&operand.value),
+        Expression::Binary { left, right, .. } => {
+            expression_is_pure(&left.value) && expression_is_pure(&right.value)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use amarok_parser::parse_program;
+
+    #[test]
+    fn a_nested_assignment_to_a_parameter_warns_once() {
+        let program = parse_program(
+            "def f(x) { x = x + 1; if (x > 0) { x = 5; } return x; }",
+        )
+        .unwrap();
+        let warnings = lint_program(&program);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "assignment to `x` in a nested block shadows a parameter of 'f' (defined at 0..55)"
+        );
+        // The warning's own span points at the nested assignment.
+        assert_eq!(warnings[0].span, Some(amarok_syntax::Span::new(35, 41)));
+    }
+
+    #[test]
+    fn top_of_body_reassignment_is_clean() {
+        let program = parse_program("def f(x) { x = x + 1; return x; }").unwrap();
+        assert!(lint_program(&program).is_empty());
+    }
+
+    #[test]
+    fn only_the_parameter_assignment_warns_inside_a_loop() {
+        let program = parse_program("def f(x) { while (x > 0) { y = x; x = x - 1; } }").unwrap();
+        let warnings = lint_program(&program);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].message.contains("`x`"));
+    }
+
+    #[test]
+    fn a_discarded_pure_expression_warns() {
+        let warnings = lint_program(&parse_program("1 + 2;").unwrap());
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(
+            warnings[0].message,
+            "this expression's result is discarded; assign it or remove the statement"
+        );
+        assert_eq!(warnings[0].span, Some(amarok_syntax::Span::new(0, 6)));
+    }
+
+    #[test]
+    fn a_call_statement_is_exempt() {
+        let program = parse_program("print(1); x = [1]; x.push(2);").unwrap();
+        assert!(lint_program(&program).is_empty());
+    }
+}
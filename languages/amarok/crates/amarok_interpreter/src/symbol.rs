@@ -0,0 +1,73 @@
+//! Identifier interning. Scope chains are walked on every variable access,
+//! and comparing a `u32` per frame beats comparing strings; the table hashes
+//! each distinct name once and hands back a copyable id.
+
+use std::collections::HashMap;
+
+/// An interned identifier. Comparing two symbols is an integer compare; the
+/// owning [`SymbolTable`] recovers the text for error messages and dumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Symbol(u32);
+
+#[derive(Debug, Default)]
+pub(crate) struct SymbolTable {
+    names: Vec<String>,
+    ids: HashMap<String, Symbol>,
+}
+
+impl SymbolTable {
+    /// The symbol for `name`, allocating one the first time it appears.
+    pub(crate) fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.ids.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), symbol);
+        symbol
+    }
+
+    /// The symbol for `name` if it has ever been interned. Read paths use
+    /// this so looking up an undefined variable doesn't grow the table.
+    pub(crate) fn lookup(&self, name: &str) -> Option<Symbol> {
+        self.ids.get(name).copied()
+    }
+
+    /// The text behind `symbol`, for error messages.
+    pub(crate) fn name(&self, symbol: Symbol) -> &str {
+        &self.names[symbol.0 as usize]
+    }
+
+    /// How many distinct names have been interned. Tests use this to show
+    /// hot loops don't grow the table per access.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.names.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interning_reuses_ids_and_round_trips_names() {
+        let mut table = SymbolTable::default();
+        let x = table.intern("x");
+        let y = table.intern("y");
+        assert_ne!(x, y);
+        assert_eq!(table.intern("x"), x);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.name(x), "x");
+        assert_eq!(table.name(y), "y");
+    }
+
+    #[test]
+    fn lookup_never_allocates_a_symbol() {
+        let mut table = SymbolTable::default();
+        assert_eq!(table.lookup("missing"), None);
+        assert_eq!(table.len(), 0);
+        let x = table.intern("x");
+        assert_eq!(table.lookup("x"), Some(x));
+    }
+}
@@ -0,0 +1,558 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use amarok_syntax::ast::Statement;
+use amarok_syntax::Spanned;
+
+/// A first-class function value, produced by evaluating a `fn(...) { ... }`
+/// expression: the parameters and body together with the local bindings that
+/// were visible where the lambda was written.
+#[derive(Debug)]
+pub struct FunctionValue {
+    pub parameters: Vec<String>,
+    pub body: Vec<Spanned<Statement>>,
+    /// The non-global bindings captured at creation, by value. Globals are
+    /// not captured; the body reads them live through the scope chain.
+    pub captured: Vec<(String, Value)>,
+}
+
+/// A runtime value produced by evaluating an Amarok expression.
+///
+/// Arrays and maps are reference types: cloning a `Value` clones the `Rc`, so
+/// two variables can alias the same underlying collection — in scripts,
+/// `b = a;` aliases and the `copy()`/`deep_copy()` builtins opt into copying.
+/// Map entries keep their insertion order.
+#[derive(Debug, Clone)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    /// An integer that outgrew `i64`. Only constructed under the `bigint`
+    /// feature, where overflowing arithmetic promotes into it; any result
+    /// that fits `i64` demotes back to `Integer`.
+    #[cfg(feature = "bigint")]
+    BigInteger(num_bigint::BigInt),
+    Float(f64),
+    Boolean(bool),
+    /// A single Unicode scalar value; indexing a string yields one of these.
+    Char(char),
+    String(String),
+    Array(Rc<RefCell<Vec<Value>>>),
+    Map(Rc<RefCell<Vec<(String, Value)>>>),
+    Function(Rc<FunctionValue>),
+    /// An explicit shared mutable box, built with the `cell()` builtin and
+    /// read/written with `get()`/`set()`. Scalars stay value types; a cell is
+    /// the opt-in way to share one mutable slot between closures. Like the
+    /// collections it is plain reference counting, so a cycle through a cell
+    /// leaks.
+    Ref(Rc<RefCell<Value>>),
+}
+
+impl Value {
+    /// Build an owned array value from plain elements.
+    pub fn array(elements: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(elements)))
+    }
+
+    /// Build an owned map value from key/value pairs, keeping their order.
+    pub fn map(entries: Vec<(String, Value)>) -> Self {
+        Value::Map(Rc::new(RefCell::new(entries)))
+    }
+
+    /// Build a fresh cell holding `value`.
+    pub fn cell(value: Value) -> Self {
+        Value::Ref(Rc::new(RefCell::new(value)))
+    }
+
+    /// The value's type, by the name scripts use in `let` annotations.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Integer(_) => "int",
+            #[cfg(feature = "bigint")]
+            Value::BigInteger(_) => "int",
+            Value::Float(_) => "float",
+            Value::Boolean(_) => "bool",
+            Value::Char(_) => "char",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Map(_) => "map",
+            Value::Function(_) => "function",
+            Value::Ref(_) => "ref",
+        }
+    }
+
+    /// Render for debugging surfaces — the REPL echo and assertion messages —
+    /// where `"5"` must stay distinguishable from `5`. Strings and chars are
+    /// quoted; `print` uses [`format_value`] instead.
+    pub fn repr(&self) -> String {
+        render(self, true, NumberFormat::Plain, &mut Vec::new())
+    }
+
+    /// Truthiness, as conditions, `and`/`or`/`not`, and `filter` see it:
+    ///
+    /// - `null` and `false` are false;
+    /// - a number is false exactly when it is zero;
+    /// - a string, array, or map is false exactly when it is empty;
+    /// - everything else — `true`, any char, functions, cells — is true.
+    ///
+    /// A cell is a box, not its contents: it is always truthy, like any
+    /// other non-empty container; scripts test `get(c)` for the contents.
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Null => false,
+            Value::Boolean(b) => *b,
+            Value::Integer(n) => *n != 0,
+            // Zero always demotes to `Integer`, so a big integer is never
+            // zero.
+            #[cfg(feature = "bigint")]
+            Value::BigInteger(_) => true,
+            Value::Float(x) => *x != 0.0,
+            Value::Char(_) => true,
+            Value::String(s) => !s.is_empty(),
+            Value::Array(elements) => !elements.borrow().is_empty(),
+            Value::Map(entries) => !entries.borrow().is_empty(),
+            Value::Function(_) => true,
+            Value::Ref(_) => true,
+        }
+    }
+}
+
+// Conversions for embedders: plain Rust values go in with `From`, and come
+// back out with `TryFrom`, which errors when the value holds something else.
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Integer(n)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::String(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::String(s.to_string())
+    }
+}
+
+/// A failed `TryFrom<Value>` conversion, naming the requested type and what
+/// the value actually held (by the script-facing type names).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueConversionError {
+    pub expected: &'static str,
+    pub actual: &'static str,
+}
+
+impl std::fmt::Display for ValueConversionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "expected {}, got {}", self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for ValueConversionError {}
+
+impl TryFrom<Value> for i64 {
+    type Error = ValueConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Integer(n) => Ok(n),
+            // A big integer is "int" to scripts but never fits an `i64` (it
+            // would have demoted), so say why instead of "got int".
+            #[cfg(feature = "bigint")]
+            Value::BigInteger(_) => Err(ValueConversionError {
+                expected: "int",
+                actual: "an int out of i64 range",
+            }),
+            other => Err(ValueConversionError {
+                expected: "int",
+                actual: other.type_name(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for bool {
+    type Error = ValueConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::Boolean(b) => Ok(b),
+            other => Err(ValueConversionError {
+                expected: "bool",
+                actual: other.type_name(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Value> for String {
+    type Error = ValueConversionError;
+
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::String(s) => Ok(s),
+            other => Err(ValueConversionError {
+                expected: "string",
+                actual: other.type_name(),
+            }),
+        }
+    }
+}
+
+/// Equality is structural: two collections are equal when their contents
+/// are. A pointer check first makes `a == a` cheap, and the pairs currently
+/// under comparison are tracked so two *distinct* cyclic structures compare
+/// in finite time instead of recursing forever — mirroring how [`render`]
+/// prints `[...]` for a collection it is already inside.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        values_equal(self, other, &mut Vec::new())
+    }
+}
+
+/// The recursion behind [`PartialEq`]. `visiting` holds the collection pairs
+/// currently being compared; re-meeting one means the comparison has gone
+/// around a cycle, and the pair is taken as equal — any real difference
+/// between the structures still surfaces somewhere off the cycle.
+fn values_equal(
+    left: &Value,
+    right: &Value,
+    visiting: &mut Vec<(*const (), *const ())>,
+) -> bool {
+    match (left, right) {
+        (Value::Null, Value::Null) => true,
+        (Value::Integer(a), Value::Integer(b)) => a == b,
+        // Mixed `Integer`/`BigInteger` pairs are never equal: a value in
+        // `i64` range always uses the `Integer` representation.
+        #[cfg(feature = "bigint")]
+        (Value::BigInteger(a), Value::BigInteger(b)) => a == b,
+        (Value::Float(a), Value::Float(b)) => a == b,
+        (Value::Boolean(a), Value::Boolean(b)) => a == b,
+        (Value::Char(a), Value::Char(b)) => a == b,
+        (Value::String(a), Value::String(b)) => a == b,
+        (Value::Array(a), Value::Array(b)) => {
+            if Rc::ptr_eq(a, b) {
+                return true;
+            }
+            let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+            if visiting.contains(&pair) {
+                return true;
+            }
+            visiting.push(pair);
+            let (a, b) = (a.borrow(), b.borrow());
+            let equal = a.len() == b.len()
+                && a.iter()
+                    .zip(b.iter())
+                    .all(|(x, y)| values_equal(x, y, visiting));
+            visiting.pop();
+            equal
+        }
+        (Value::Map(a), Value::Map(b)) => {
+            if Rc::ptr_eq(a, b) {
+                return true;
+            }
+            let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+            if visiting.contains(&pair) {
+                return true;
+            }
+            visiting.push(pair);
+            let (a, b) = (a.borrow(), b.borrow());
+            let equal = a.len() == b.len()
+                && a.iter().zip(b.iter()).all(|((key_a, value_a), (key_b, value_b))| {
+                    key_a == key_b && values_equal(value_a, value_b, visiting)
+                });
+            visiting.pop();
+            equal
+        }
+        (Value::Ref(a), Value::Ref(b)) => {
+            if Rc::ptr_eq(a, b) {
+                return true;
+            }
+            let pair = (Rc::as_ptr(a) as *const (), Rc::as_ptr(b) as *const ());
+            if visiting.contains(&pair) {
+                return true;
+            }
+            visiting.push(pair);
+            let equal = values_equal(&a.borrow(), &b.borrow(), visiting);
+            visiting.pop();
+            equal
+        }
+        // A function is only equal to itself; two lambdas with the same
+        // text are still distinct values.
+        (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+        _ => false,
+    }
+}
+
+/// How `print` renders integer and float digits. Further locales can be
+/// added as variants; everything funnels through [`format_value_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// Plain digit runs: `1000000`.
+    #[default]
+    Plain,
+    /// A comma every three digits: `1,000,000`. Floats group their integer
+    /// part only.
+    Grouped,
+}
+
+/// Render a value the way `print` shows it: strings and chars are unquoted.
+pub fn format_value(value: &Value) -> String {
+    format_value_with(value, NumberFormat::Plain)
+}
+
+/// [`format_value`] with an explicit number format, for hosts that set one
+/// on the interpreter.
+pub fn format_value_with(value: &Value, numbers: NumberFormat) -> String {
+    render(value, false, numbers, &mut Vec::new())
+}
+
+/// A whole-number float keeps its decimal point (`1.0`, not `1`), so the
+/// float-ness survives a round trip through `print`. Everything else uses
+/// Rust's shortest-roundtrip rendering, which already trims decimal noise.
+fn format_float(x: f64) -> String {
+    if x.is_finite() && x.fract() == 0.0 {
+        format!("{:.1}", x)
+    } else {
+        x.to_string()
+    }
+}
+
+/// Insert `,` separators into the digit run of `rendered` — everything up to
+/// the first non-digit (skipping a leading `-`), so `-1234.5` becomes
+/// `-1,234.5` and digit-free renderings like `inf` pass through.
+fn group_digits(rendered: &str) -> String {
+    let (sign, rest) = match rendered.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", rendered),
+    };
+    let digits_end = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+    let (digits, tail) = rest.split_at(digits_end);
+    let mut grouped = String::with_capacity(rendered.len() + digits.len() / 3);
+    grouped.push_str(sign);
+    for (position, digit) in digits.chars().enumerate() {
+        if position > 0 && (digits.len() - position) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped.push_str(tail);
+    grouped
+}
+
+fn format_number(rendered: String, numbers: NumberFormat) -> String {
+    match numbers {
+        NumberFormat::Plain => rendered,
+        NumberFormat::Grouped => group_digits(&rendered),
+    }
+}
+
+/// Shared renderer behind [`format_value`] and [`Value::repr`]. `visiting`
+/// holds the collections currently being rendered, so a self-referential
+/// structure prints `[...]`/`{...}` instead of recursing forever.
+fn render(
+    value: &Value,
+    quote_strings: bool,
+    numbers: NumberFormat,
+    visiting: &mut Vec<*const ()>,
+) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Integer(n) => format_number(n.to_string(), numbers),
+        #[cfg(feature = "bigint")]
+        Value::BigInteger(n) => format_number(n.to_string(), numbers),
+        Value::Float(x) => format_number(format_float(*x), numbers),
+        Value::Boolean(b) => b.to_string(),
+        Value::Char(c) if quote_strings => format!("{:?}", c),
+        Value::Char(c) => c.to_string(),
+        Value::String(s) if quote_strings => format!("{:?}", s),
+        Value::String(s) => s.clone(),
+        Value::Array(elements) => {
+            let pointer = Rc::as_ptr(elements) as *const ();
+            if visiting.contains(&pointer) {
+                return "[...]".to_string();
+            }
+            visiting.push(pointer);
+            let rendered: Vec<String> = elements
+                .borrow()
+                .iter()
+                .map(|element| render(element, quote_strings, numbers, visiting))
+                .collect();
+            visiting.pop();
+            format!("[{}]", rendered.join(", "))
+        }
+        Value::Function(function) => format!("<fn({})>", function.parameters.join(", ")),
+        Value::Ref(inner) => {
+            let pointer = Rc::as_ptr(inner) as *const ();
+            if visiting.contains(&pointer) {
+                return "cell(...)".to_string();
+            }
+            visiting.push(pointer);
+            let rendered = render(&inner.borrow(), quote_strings, numbers, visiting);
+            visiting.pop();
+            format!("cell({})", rendered)
+        }
+        Value::Map(entries) => {
+            let pointer = Rc::as_ptr(entries) as *const ();
+            if visiting.contains(&pointer) {
+                return "{...}".to_string();
+            }
+            visiting.push(pointer);
+            let rendered: Vec<String> = entries
+                .borrow()
+                .iter()
+                .map(|(key, value)| {
+                    format!(
+                        "{}: {}",
+                        key,
+                        render(value, quote_strings, numbers, visiting)
+                    )
+                })
+                .collect();
+            visiting.pop();
+            format!("{{{}}}", rendered.join(", "))
+        }
+    }
+}
+
+/// Wrap a big-integer result, demoting to `Integer` when it fits `i64` so
+/// in-range values always use the fast representation (and compare equal to
+/// plain integers).
+#[cfg(feature = "bigint")]
+pub(crate) fn integer_from_big(n: num_bigint::BigInt) -> Value {
+    match i64::try_from(&n) {
+        Ok(small) => Value::Integer(small),
+        Err(_) => Value::BigInteger(n),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_and_try_from_round_trip() {
+        assert_eq!(i64::try_from(Value::from(5i64)), Ok(5));
+        assert_eq!(bool::try_from(Value::from(true)), Ok(true));
+        assert_eq!(
+            String::try_from(Value::from("hello")),
+            Ok("hello".to_string())
+        );
+        assert_eq!(
+            String::try_from(Value::from("owned".to_string())),
+            Ok("owned".to_string())
+        );
+    }
+
+    #[test]
+    fn try_from_names_both_types_on_a_mismatch() {
+        let error = i64::try_from(Value::from("5")).unwrap_err();
+        assert_eq!(error.to_string(), "expected int, got string");
+        let error = bool::try_from(Value::Null).unwrap_err();
+        assert_eq!(error.to_string(), "expected bool, got null");
+        let error = String::try_from(Value::Integer(1)).unwrap_err();
+        assert_eq!(error.to_string(), "expected string, got int");
+    }
+
+    #[test]
+    fn grouping_reaches_numbers_inside_collections_but_not_repr() {
+        let value = Value::array(vec![Value::Integer(1_000_000), Value::Float(2.5)]);
+        assert_eq!(
+            format_value_with(&value, NumberFormat::Grouped),
+            "[1,000,000, 2.5]"
+        );
+        // `repr` is a debugging surface and always stays plain.
+        assert_eq!(value.repr(), "[1000000, 2.5]");
+    }
+
+    #[test]
+    fn non_finite_floats_are_not_grouped() {
+        let value = Value::Float(f64::INFINITY);
+        assert_eq!(format_value_with(&value, NumberFormat::Grouped), "inf");
+    }
+
+    #[test]
+    fn truthiness_follows_the_documented_table() {
+        let cases = [
+            (Value::Null, false),
+            (Value::Boolean(false), false),
+            (Value::Boolean(true), true),
+            (Value::Integer(0), false),
+            (Value::Integer(-1), true),
+            (Value::Float(0.0), false),
+            (Value::Float(0.5), true),
+            (Value::Char('\0'), true),
+            (Value::String(String::new()), false),
+            (Value::String(" ".to_string()), true),
+            (Value::array(Vec::new()), false),
+            (Value::array(vec![Value::Integer(0)]), true),
+            (Value::map(Vec::new()), false),
+            (Value::cell(Value::Null), true),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(value.is_truthy(), expected, "value {}", value.repr());
+        }
+    }
+
+    #[test]
+    fn repr_quotes_strings_where_display_does_not() {
+        let value = Value::String("5".to_string());
+        assert_eq!(format_value(&value), "5");
+        assert_eq!(value.repr(), "\"5\"");
+    }
+
+    #[test]
+    fn repr_quotes_strings_inside_collections() {
+        let value = Value::array(vec![Value::Integer(1), Value::String("x".to_string())]);
+        assert_eq!(format_value(&value), "[1, x]");
+        assert_eq!(value.repr(), "[1, \"x\"]");
+    }
+
+    #[test]
+    fn nested_arrays_compare_structurally() {
+        let nested = || Value::array(vec![Value::Integer(1), Value::array(vec![Value::Integer(2)])]);
+        assert_eq!(nested(), nested());
+        assert_ne!(nested(), Value::array(vec![Value::Integer(1)]));
+    }
+
+    #[test]
+    fn self_referential_arrays_print_an_ellipsis() {
+        let value = Value::array(vec![Value::Integer(1)]);
+        if let Value::Array(elements) = &value {
+            elements.borrow_mut().push(value.clone());
+        }
+        assert_eq!(format_value(&value), "[1, [...]]");
+        // The pointer fast path keeps self-comparison from recursing.
+        assert_eq!(value, value.clone());
+    }
+
+    /// `[n, <self>]` — a one-element cycle for the equality tests.
+    fn cycle(n: i64) -> Value {
+        let value = Value::array(vec![Value::Integer(n)]);
+        if let Value::Array(elements) = &value {
+            elements.borrow_mut().push(value.clone());
+        }
+        value
+    }
+
+    #[test]
+    fn distinct_cycles_compare_without_recursing_forever() {
+        // Two separately built cycles of the same shape: everything off the
+        // cycle matches, and the cycle itself is taken as equal.
+        assert_eq!(cycle(1), cycle(1));
+        // A difference off the cycle still shows up.
+        assert_ne!(cycle(1), cycle(2));
+    }
+}
@@ -0,0 +1,2092 @@
+//! The pest-based parser for the Amarok language.
+//!
+//! The grammar lives in `amarok.pest`; this crate turns the pest parse tree
+//! into the spanned AST defined in `amarok_syntax`.
+
+use amarok_syntax::ast::{
+    BinaryOperator, Expression, MatchArm, Program, Statement, TypeName, UnaryOperator,
+};
+use amarok_syntax::{Span, Spanned};
+use pest::iterators::Pair;
+use pest::Parser;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "amarok.pest"]
+struct AmarokParser;
+
+/// An error produced while parsing, pointing at the offending source range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Span,
+}
+
+/// The mapping from parse error messages to stable public codes, matched by
+/// prefix; anything unlisted is the generic syntax error `P0001`. Codes
+/// never change meaning once assigned.
+const ERROR_CODES: &[(&str, &str)] = &[
+    ("integer literal is too large", "P0002"),
+    ("`print` is a function", "P0003"),
+    ("unknown type name:", "P0004"),
+];
+
+impl ParseError {
+    pub fn new(message: impl Into<String>, span: Span) -> Self {
+        Self {
+            message: message.into(),
+            span,
+        }
+    }
+
+    /// The stable diagnostic code for this error, so docs and tooling can
+    /// reference it across versions.
+    pub fn code(&self) -> &'static str {
+        ERROR_CODES
+            .iter()
+            .find(|(prefix, _)| self.message.starts_with(prefix))
+            .map(|(_, code)| *code)
+            .unwrap_or("P0001")
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} at {}..{}",
+            self.message, self.span.start, self.span.end
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Parse a full source file into a [`Program`].
+///
+/// A leading `#!...` line is ignored, so scripts run via
+/// `#!/usr/bin/env amarok` parse without the interpreter stripping it first.
+pub fn parse_program(source: &str) -> Result<Program, ParseError> {
+    check_nesting_depth(source)?;
+    let source = blank_shebang(source);
+    let mut pairs = AmarokParser::parse(Rule::program, &source)
+        .map_err(|error| convert_pest_error(error, &source))?;
+    build_program(pairs.next().expect("grammar guarantees a program pair"))
+}
+
+/// The deepest bracket nesting a source file may use. Grammar recursion (and
+/// AST construction after it) descends several frames per open bracket, and
+/// a debug build on a default 2 MiB test thread falls over somewhere past 40
+/// levels — so the cap has to sit well below that, not merely below
+/// "pathological". Hand-written code rarely nests past ten.
+const MAX_NESTING_DEPTH: usize = 32;
+
+/// Reject `source` when `(`/`[`/`{` nest deeper than [`MAX_NESTING_DEPTH`],
+/// before any recursive parsing runs. The scan skips string and char
+/// literals and `//` comments; it is a conservative guard, not a balance
+/// checker — closers below zero are ignored and the real parser still
+/// diagnoses mismatches.
+fn check_nesting_depth(source: &str) -> Result<(), ParseError> {
+    let mut depth = 0usize;
+    let mut characters = source.char_indices().peekable();
+    while let Some((index, character)) = characters.next() {
+        match character {
+            '(' | '[' | '{' => {
+                depth += 1;
+                if depth > MAX_NESTING_DEPTH {
+                    return Err(ParseError::new(
+                        format!(
+                            "brackets nested deeper than {} levels",
+                            MAX_NESTING_DEPTH
+                        ),
+                        Span::new(index, index + character.len_utf8()),
+                    ));
+                }
+            }
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            quote @ ('"' | '\'') => {
+                while let Some((_, inner)) = characters.next() {
+                    match inner {
+                        '\\' => {
+                            characters.next();
+                        }
+                        inner if inner == quote => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if matches!(characters.peek(), Some((_, '/'))) => {
+                for (_, inner) in characters.by_ref() {
+                    if inner == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Replace a leading `#!...` line — up to, not including, its newline — with
+/// spaces. The substitution is one space per byte, so spans from the
+/// rewritten source index straight into the original file.
+fn blank_shebang(source: &str) -> std::borrow::Cow<'_, str> {
+    if !source.starts_with("#!") {
+        return std::borrow::Cow::Borrowed(source);
+    }
+    let line_length = source.find('\n').unwrap_or(source.len());
+    let mut rewritten = " ".repeat(line_length);
+    rewritten.push_str(&source[line_length..]);
+    std::borrow::Cow::Owned(rewritten)
+}
+
+/// Parse as much of `source` as possible, collecting errors instead of
+/// stopping at the first one.
+///
+/// pest parses are all-or-nothing, so recovery works statement by statement:
+/// after a failed statement the parser records the error, skips past the next
+/// `;` or `}`, and tries again. All successfully parsed statements are
+/// returned alongside every error.
+pub fn parse_program_with_recovery(source: &str) -> (Program, Vec<ParseError>) {
+    // The common case — a clean file — goes through the normal parse.
+    if let Ok(program) = parse_program(source) {
+        return (program, Vec::new());
+    }
+
+    // Over-deep nesting would overflow the stack just as surely statement by
+    // statement, so it short-circuits recovery with its one error.
+    if let Err(error) = check_nesting_depth(source) {
+        return (Program { statements: Vec::new() }, vec![error]);
+    }
+
+    // Blank the shebang here too, so statement-by-statement recovery never
+    // reports the `#!` line itself as an error.
+    let source = &*blank_shebang(source);
+    let mut statements = Vec::new();
+    let mut errors = Vec::new();
+    let mut offset = 0;
+
+    loop {
+        offset = skip_trivia(source, offset);
+        if offset >= source.len() {
+            break;
+        }
+        match AmarokParser::parse(Rule::statement, &source[offset..]) {
+            Ok(mut pairs) => {
+                let pair = pairs.next().expect("a successful parse has a pair");
+                let consumed = pair.as_span().end();
+                match build_statement(pair) {
+                    Ok(mut statement) => {
+                        shift_statement(&mut statement, offset);
+                        statements.push(statement);
+                        offset += consumed.max(1);
+                    }
+                    Err(mut error) => {
+                        shift_span(&mut error.span, offset);
+                        errors.push(error);
+                        offset = resync(source, offset);
+                    }
+                }
+            }
+            Err(pest_error) => {
+                let mut error = convert_pest_error(pest_error, &source[offset..]);
+                shift_span(&mut error.span, offset);
+                errors.push(error);
+                offset = resync(source, offset);
+            }
+        }
+    }
+
+    (Program { statements }, errors)
+}
+
+/// Like [`parse_program`], but a newline may terminate a statement, so quick
+/// scripts can drop the trailing `;`.
+///
+/// Explicit semicolons keep working. The rewrite is heuristic: a newline
+/// becomes a terminator only when the line so far looks complete (it ends in
+/// an identifier, literal, `)` or `]` at bracket depth zero) and the next
+/// line doesn't continue it (it doesn't open a block or start with an
+/// operator, `else`, …). Expressions split across lines inside parentheses
+/// or brackets are untouched. Known limits: multiline map literals and lines
+/// ending in a `//` comment still need explicit semicolons.
+pub fn parse_program_with_implicit_semicolons(source: &str) -> Result<Program, ParseError> {
+    parse_program(&insert_implicit_semicolons(source))
+}
+
+/// Replace each statement-terminating newline with `;`. The substitution is
+/// one char for one char, so spans from the rewritten source index straight
+/// into the original.
+fn insert_implicit_semicolons(source: &str) -> String {
+    let characters: Vec<char> = source.chars().collect();
+    let mut result = String::with_capacity(source.len());
+    let mut bracket_depth = 0usize;
+    let mut in_string = false;
+    let mut in_char = false;
+    let mut in_comment = false;
+    let mut escaped = false;
+    // The last non-whitespace char emitted, and the word it ends (to spot
+    // trailing keywords like `and` that promise a continuation).
+    let mut last_significant: Option<char> = None;
+    let mut last_word = String::new();
+
+    for (position, &character) in characters.iter().enumerate() {
+        if in_string || in_char {
+            if escaped {
+                escaped = false;
+            } else if character == '\\' {
+                escaped = true;
+            } else if (in_string && character == '"') || (in_char && character == '\'') {
+                in_string = false;
+                in_char = false;
+                last_significant = Some(character);
+                last_word.clear();
+            }
+            result.push(character);
+            continue;
+        }
+        if in_comment {
+            if character == '\n' {
+                in_comment = false;
+            }
+            result.push(character);
+            continue;
+        }
+
+        match character {
+            '"' => in_string = true,
+            '\'' => in_char = true,
+            '/' if characters.get(position + 1) == Some(&'/') => {
+                in_comment = true;
+            }
+            '(' | '[' => bracket_depth += 1,
+            ')' | ']' => bracket_depth = bracket_depth.saturating_sub(1),
+            '\n' if bracket_depth == 0
+                && line_looks_complete(last_significant, &last_word)
+                && next_line_starts_a_statement(&characters[position + 1..]) =>
+            {
+                result.push(';');
+                last_significant = Some(';');
+                last_word.clear();
+                continue;
+            }
+            _ => {}
+        }
+
+        if character.is_whitespace() {
+            last_word.clear();
+        } else {
+            last_significant = Some(character);
+            if character.is_alphanumeric() || character == '_' {
+                last_word.push(character);
+            } else {
+                last_word.clear();
+            }
+        }
+        result.push(character);
+    }
+    result
+}
+
+/// Could the line ending here be a complete statement?
+fn line_looks_complete(last_significant: Option<char>, last_word: &str) -> bool {
+    let complete_ending = matches!(
+        last_significant,
+        Some(c) if c.is_alphanumeric() || matches!(c, '_' | '"' | '\'' | ')' | ']')
+    );
+    // A trailing word operator or introducer promises more to come.
+    let continuation_word =
+        matches!(last_word, "else" | "def" | "and" | "or" | "not" | "in" | "div");
+    complete_ending && !continuation_word
+}
+
+/// Does the text after a newline begin a fresh statement, rather than
+/// continue the previous line?
+fn next_line_starts_a_statement(rest: &[char]) -> bool {
+    let mut position = 0;
+    loop {
+        while position < rest.len() && rest[position].is_whitespace() {
+            position += 1;
+        }
+        // Skip whole comment lines so insertion still happens around them.
+        if rest.get(position) == Some(&'/') && rest.get(position + 1) == Some(&'/') {
+            while position < rest.len() && rest[position] != '\n' {
+                position += 1;
+            }
+            continue;
+        }
+        break;
+    }
+    match rest.get(position) {
+        // End of input: terminate the final statement.
+        None => true,
+        // Operators, separators, and block openers continue the line.
+        Some('+' | '-' | '*' | '/' | '%' | '=' | '<' | '>' | '!' | '&' | '|' | '?' | ':' | '.'
+        | ',' | ')' | ']' | '{') => false,
+        Some(_) => {
+            let word: String = rest[position..]
+                .iter()
+                .take_while(|c| c.is_alphanumeric() || **c == '_')
+                .collect();
+            word != "else"
+        }
+    }
+}
+
+/// Skip whitespace and plain `//` comments (doc comments belong to the next
+/// statement, so they stay).
+fn skip_trivia(source: &str, mut offset: usize) -> usize {
+    let bytes = source.as_bytes();
+    loop {
+        while offset < bytes.len() && bytes[offset].is_ascii_whitespace() {
+            offset += 1;
+        }
+        if source[offset..].starts_with("//") && !source[offset..].starts_with("///") {
+            while offset < bytes.len() && bytes[offset] != b'\n' {
+                offset += 1;
+            }
+            continue;
+        }
+        return offset;
+    }
+}
+
+/// Advance past the next `;` or `}` so parsing can continue after an error.
+fn resync(source: &str, offset: usize) -> usize {
+    match source[offset..].find([';', '}']) {
+        Some(position) => offset + position + 1,
+        None => source.len(),
+    }
+}
+
+fn shift_span(span: &mut Span, offset: usize) {
+    span.start += offset;
+    span.end += offset;
+}
+
+fn shift_statement(statement: &mut Spanned<Statement>, offset: usize) {
+    map_statement_spans(statement, &|span| shift_span(span, offset));
+}
+
+fn map_statement_spans(statement: &mut Spanned<Statement>, apply: &impl Fn(&mut Span)) {
+    apply(&mut statement.span);
+    match &mut statement.value {
+        Statement::Assignment { value, .. } | Statement::Let { value, .. } => {
+            map_expression_spans(value, apply)
+        }
+        Statement::Desugared { statement, .. } => map_statement_spans(statement, apply),
+        Statement::IndexAssignment {
+            target,
+            index,
+            value,
+        } => {
+            map_expression_spans(target, apply);
+            map_expression_spans(index, apply);
+            map_expression_spans(value, apply);
+        }
+        Statement::FunctionDefinition { body, .. } | Statement::Block(body) => {
+            for statement in body {
+                map_statement_spans(statement, apply);
+            }
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            map_expression_spans(condition, apply);
+            for statement in then_branch {
+                map_statement_spans(statement, apply);
+            }
+            if let Some(else_branch) = else_branch {
+                for statement in else_branch {
+                    map_statement_spans(statement, apply);
+                }
+            }
+        }
+        Statement::Match { scrutinee, arms } => {
+            map_expression_spans(scrutinee, apply);
+            for arm in arms {
+                if let Some(pattern) = &mut arm.pattern {
+                    map_expression_spans(pattern, apply);
+                }
+                for statement in &mut arm.body {
+                    map_statement_spans(statement, apply);
+                }
+            }
+        }
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+        } => {
+            map_expression_spans(condition, apply);
+            for statement in body {
+                map_statement_spans(statement, apply);
+            }
+            if let Some(else_branch) = else_branch {
+                for statement in else_branch {
+                    map_statement_spans(statement, apply);
+                }
+            }
+        }
+        Statement::ForIn {
+            iterable, body, ..
+        } => {
+            map_expression_spans(iterable, apply);
+            for statement in body {
+                map_statement_spans(statement, apply);
+            }
+        }
+        Statement::Return { value } => {
+            if let Some(value) = value {
+                map_expression_spans(value, apply);
+            }
+        }
+        Statement::Break | Statement::Continue => {}
+        Statement::Expression(expression) => map_expression_spans(expression, apply),
+    }
+}
+
+fn map_expression_spans(expression: &mut Spanned<Expression>, apply: &impl Fn(&mut Span)) {
+    apply(&mut expression.span);
+    match &mut expression.value {
+        Expression::Unary { operand, .. } => map_expression_spans(operand, apply),
+        Expression::Binary { left, right, .. } => {
+            map_expression_spans(left, apply);
+            map_expression_spans(right, apply);
+        }
+        Expression::FunctionCall { arguments, .. } => {
+            for argument in arguments {
+                map_expression_spans(argument, apply);
+            }
+        }
+        Expression::MethodCall {
+            receiver,
+            arguments,
+            ..
+        } => {
+            map_expression_spans(receiver, apply);
+            for argument in arguments {
+                map_expression_spans(argument, apply);
+            }
+        }
+        Expression::Array(elements) | Expression::Tuple(elements) => {
+            for element in elements {
+                map_expression_spans(element, apply);
+            }
+        }
+        Expression::Map(entries) => {
+            for (_, value) in entries {
+                map_expression_spans(value, apply);
+            }
+        }
+        Expression::Index { target, index } => {
+            map_expression_spans(target, apply);
+            map_expression_spans(index, apply);
+        }
+        Expression::Lambda { body, .. } => {
+            for statement in body {
+                map_statement_spans(statement, apply);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Parse a single expression, for REPL-style evaluation. The whole input
+/// must be one expression: statement syntax like `x = 1;` is rejected with a
+/// pointed error instead of a partial parse.
+pub fn parse_expression(source: &str) -> Result<Spanned<Expression>, ParseError> {
+    check_nesting_depth(source)?;
+    let mut pairs = match AmarokParser::parse(Rule::expression_input, source) {
+        Ok(pairs) => pairs,
+        Err(error) => {
+            // When the input is actually a statement, say so directly rather
+            // than listing every token an expression could continue with.
+            if AmarokParser::parse(Rule::program, source).is_ok() {
+                return Err(ParseError::new(
+                    "expected an expression, but found a statement",
+                    Span::new(0, source.len()),
+                ));
+            }
+            return Err(convert_pest_error(error, source));
+        }
+    };
+    let expression = pairs
+        .next()
+        .expect("grammar guarantees an expression_input pair")
+        .into_inner()
+        .find(|inner| inner.as_rule() == Rule::expression)
+        .expect("expression_input contains an expression");
+    build_expression(expression)
+}
+
+/// How many placeholders a best-effort parse inserts before giving up.
+/// Each adopted placeholder reports its own error, so this also caps the
+/// number of errors returned.
+const MAX_RECOVERY_PLACEHOLDERS: usize = 4;
+
+/// Like [`parse_expression`], but best-effort for editors working on
+/// partial input: a recoverable hole — a missing operand, a missing call
+/// argument — is filled with a placeholder and the parse retried, so
+/// `1 + ` yields the binary tree with a placeholder right operand plus one
+/// error. A placeholder appears in the tree as a `null` literal with an
+/// empty span at the hole; no parsed literal ever has an empty span, so
+/// tools can tell it apart from a written `null`. Input too broken to
+/// patch returns `None` alongside the errors found on the way.
+pub fn parse_expression_with_recovery(
+    source: &str,
+) -> (Option<Spanned<Expression>>, Vec<ParseError>) {
+    match parse_expression(source) {
+        Ok(expression) => (Some(expression), Vec::new()),
+        Err(first) => recover_with_placeholders(
+            source,
+            first,
+            &["null"],
+            parse_expression,
+            |expression, unshift| map_expression_spans(expression, &unshift),
+        ),
+    }
+}
+
+/// The statement counterpart of [`parse_expression_with_recovery`]: parse
+/// one statement best-effort, patching holes with `null` placeholders or a
+/// missing `;`, and report every error found on the way.
+pub fn parse_statement_with_recovery(
+    source: &str,
+) -> (Option<Spanned<Statement>>, Vec<ParseError>) {
+    match parse_single_statement(source) {
+        Ok(statement) => (Some(statement), Vec::new()),
+        Err(first) => recover_with_placeholders(
+            source,
+            first,
+            &["null", ";"],
+            parse_single_statement,
+            |statement, unshift| map_statement_spans(statement, &unshift),
+        ),
+    }
+}
+
+/// Parse exactly one statement, rejecting trailing input; the strict
+/// counterpart the recovery loop retries against.
+fn parse_single_statement(source: &str) -> Result<Spanned<Statement>, ParseError> {
+    check_nesting_depth(source)?;
+    let mut pairs = AmarokParser::parse(Rule::statement, source)
+        .map_err(|error| convert_pest_error(error, source))?;
+    let pair = pairs.next().expect("a successful parse has a pair");
+    let consumed = pair.as_span().end();
+    if !source[consumed..].trim().is_empty() {
+        return Err(ParseError::new(
+            "expected a single statement",
+            Span::new(consumed, source.len()),
+        ));
+    }
+    build_statement(pair)
+}
+
+/// The retry loop behind the `_with_recovery` parsers: insert a candidate
+/// placeholder at the error position and reparse, keeping the first
+/// candidate that parses or at least moves the error forward. On success
+/// every span in the result is mapped back to the original source, which
+/// collapses each placeholder to an empty span at its hole.
+fn recover_with_placeholders<T>(
+    source: &str,
+    first: ParseError,
+    candidates: &[&str],
+    parse: impl Fn(&str) -> Result<T, ParseError>,
+    map_spans: impl Fn(&mut T, &dyn Fn(&mut Span)),
+) -> (Option<T>, Vec<ParseError>) {
+    // Positions and widths of adopted placeholders, each in the coordinates
+    // of the source as patched so far; undoing them in reverse order maps a
+    // patched coordinate back to the original source.
+    let mut insertions: Vec<(usize, usize)> = Vec::new();
+    let unshift_all = |span: &mut Span, insertions: &[(usize, usize)]| {
+        for &(insertion, width) in insertions.iter().rev() {
+            span.start = unshift_position(span.start, insertion, width);
+            span.end = unshift_position(span.end, insertion, width);
+        }
+    };
+    let mut patched = source.to_string();
+    let mut errors = vec![first.clone()];
+    let mut last_error = first;
+    while insertions.len() < MAX_RECOVERY_PLACEHOLDERS {
+        let at = last_error.span.start.min(patched.len());
+        let mut adopted = None;
+        for candidate in candidates {
+            let mut trial = patched.clone();
+            trial.insert_str(at, candidate);
+            match parse(&trial) {
+                Ok(mut parsed) => {
+                    insertions.push((at, candidate.len()));
+                    map_spans(&mut parsed, &|span| unshift_all(span, &insertions));
+                    return (Some(parsed), errors);
+                }
+                // Only an error past the insertion point means the patch
+                // helped. Keep the first such candidate but still try the
+                // rest: a later one may parse outright — `1 +` wants `null`,
+                // but `x = 1 +null` wants the `;`, not another `null`.
+                Err(error) if error.span.start > at && adopted.is_none() => {
+                    adopted = Some((trial, candidate.len(), error));
+                }
+                Err(_) => {}
+            }
+        }
+        let Some((trial, width, error)) = adopted else {
+            return (None, errors);
+        };
+        patched = trial;
+        insertions.push((at, width));
+        let mut reported = error.clone();
+        unshift_all(&mut reported.span, &insertions);
+        errors.push(reported);
+        last_error = error;
+    }
+    (None, errors)
+}
+
+/// Undo one placeholder insertion: coordinates past the inserted text move
+/// back by its width, and anything inside it collapses onto the insertion
+/// point.
+fn unshift_position(position: usize, insertion: usize, width: usize) -> usize {
+    if position <= insertion {
+        position
+    } else {
+        position.saturating_sub(width).max(insertion)
+    }
+}
+
+fn convert_pest_error(error: pest::error::Error<Rule>, source: &str) -> ParseError {
+    let span = match error.location {
+        pest::error::InputLocation::Pos(pos) => Span::new(pos, (pos + 1).min(source.len())),
+        pest::error::InputLocation::Span((start, end)) => Span::new(start, end),
+    };
+    if let Some(suggestion) = suggest_print_call(source, span) {
+        return ParseError::new(suggestion, span);
+    }
+    if let Some(suggestion) = suggest_keyword_identifier(source, span) {
+        return ParseError::new(suggestion, span);
+    }
+    if let Some(suggestion) = suggest_condition_assignment(source, span) {
+        return ParseError::new(suggestion, span);
+    }
+    ParseError::new(error.variant.message().into_owned(), span)
+}
+
+/// Every reserved word in the grammar, mirroring the `keyword` rule.
+const KEYWORDS: &[&str] = &[
+    "and", "break", "continue", "def", "div", "else", "false", "fn", "for", "if", "in", "let",
+    "match", "not", "null", "or", "return", "true", "while",
+];
+
+/// A keyword in identifier position produces an opaque expectation list;
+/// name the real problem instead. Two shapes are covered: the error lands on
+/// the keyword itself (`def if() {}`), or just after it when the keyword was
+/// consumed as a statement opener (`if = 5;` stalls at the `=`).
+fn suggest_keyword_identifier(source: &str, span: Span) -> Option<String> {
+    let position = span.start.min(source.len());
+    let rest = &source[position..];
+    let word_at = rest
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or("");
+    let keyword = if KEYWORDS.contains(&word_at) {
+        word_at
+    } else if rest.trim_start().starts_with('=') && !rest.trim_start().starts_with("==") {
+        let line_start = source[..position]
+            .rfind('\n')
+            .map(|index| index + 1)
+            .unwrap_or(0);
+        let before = source[line_start..position].trim();
+        if !KEYWORDS.contains(&before) {
+            return None;
+        }
+        before
+    } else {
+        return None;
+    };
+    Some(format!(
+        "`{}` is a keyword and cannot be used as an identifier",
+        keyword
+    ))
+}
+
+/// Assignment is a statement, so `if (x = 5)` already fails to parse — the
+/// condition stalls at the `=`. When the error sits on a single `=` and the
+/// line opened with `if` or `while` followed by a condition, the user almost
+/// certainly meant a comparison, so say that instead of the generic
+/// expectation list. A bare `if = 5;` is the keyword-as-identifier case and
+/// is claimed by [`suggest_keyword_identifier`] first.
+fn suggest_condition_assignment(source: &str, span: Span) -> Option<String> {
+    let position = span.start.min(source.len());
+    let rest = source[position..].trim_start();
+    if !rest.starts_with('=') || rest.starts_with("==") {
+        return None;
+    }
+    let line_start = source[..position]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    let before = source[line_start..position].trim_start();
+    ["if", "while"]
+        .into_iter()
+        .any(|keyword| {
+            before.strip_prefix(keyword).is_some_and(|after| {
+                after.starts_with(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    && !after.trim().is_empty()
+            })
+        })
+        .then(|| "assignment is not allowed in a condition; did you mean `==`?".to_string())
+}
+
+/// `print x;` is a common slip for users coming from languages with a print
+/// statement. The parser consumes `print` as a variable and then stalls, so
+/// when everything before the error on its line is exactly `print`, suggest
+/// the call form instead of the generic expectation list.
+fn suggest_print_call(source: &str, span: Span) -> Option<String> {
+    let position = span.start.min(source.len());
+    let line_start = source[..position]
+        .rfind('\n')
+        .map(|index| index + 1)
+        .unwrap_or(0);
+    (source[line_start..position].trim() == "print")
+        .then(|| "`print` is a function: write `print(...)`, not `print ...;`".to_string())
+}
+
+fn span_of(pair: &Pair<Rule>) -> Span {
+    let span = pair.as_span();
+    Span::new(span.start(), span.end())
+}
+
+fn build_program(pair: Pair<Rule>) -> Result<Program, ParseError> {
+    let mut statements = Vec::new();
+    for inner in pair.into_inner() {
+        match inner.as_rule() {
+            Rule::statement => statements.push(build_statement(inner)?),
+            Rule::EOI => {}
+            // An explicit error instead of a silent skip, so a grammar change
+            // can't make statements vanish without a test failure.
+            rule => {
+                return Err(ParseError::new(
+                    format!("unexpected rule under program: {:?}", rule),
+                    span_of(&inner),
+                ))
+            }
+        }
+    }
+    Ok(Program { statements })
+}
+
+fn build_statement(pair: Pair<Rule>) -> Result<Spanned<Statement>, ParseError> {
+    let span = span_of(&pair);
+    match pair.as_rule() {
+        Rule::statement => {
+            let inner = pair
+                .into_inner()
+                .next()
+                .expect("a statement wraps exactly one concrete statement");
+            build_statement(inner)
+        }
+        Rule::function_definition => {
+            let mut name = String::new();
+            let mut parameters = Vec::new();
+            let mut body = Vec::new();
+            let mut doc_lines = Vec::new();
+            for inner in pair.into_inner() {
+                match inner.as_rule() {
+                    Rule::doc_comment => {
+                        doc_lines.push(inner.as_str().trim_start_matches('/').trim().to_string());
+                    }
+                    Rule::identifier => name = inner.as_str().to_string(),
+                    Rule::parameter_list => {
+                        parameters = inner.into_inner().map(|p| p.as_str().to_string()).collect();
+                    }
+                    Rule::block => body = build_statement_list(inner)?,
+                    _ => {}
+                }
+            }
+            let doc = if doc_lines.is_empty() {
+                None
+            } else {
+                Some(doc_lines.join("\n"))
+            };
+            Ok(Spanned::new(
+                Statement::FunctionDefinition {
+                    name,
+                    parameters,
+                    body,
+                    doc,
+                },
+                span,
+            ))
+        }
+        Rule::if_statement => build_if_statement(pair),
+        Rule::while_statement => {
+            let mut inner = pair.into_inner();
+            inner.next(); // kw_while
+            let condition = build_expression(inner.next().expect("while has a condition"))?;
+            let body = build_statement_list(inner.next().expect("while has a body"))?;
+            let else_branch = match inner.next() {
+                Some(while_else) => {
+                    let mut else_inner = while_else.into_inner();
+                    else_inner.next(); // kw_else
+                    Some(build_statement_list(
+                        else_inner.next().expect("while-else has a body"),
+                    )?)
+                }
+                None => None,
+            };
+            Ok(Spanned::new(
+                Statement::While {
+                    condition,
+                    body,
+                    else_branch,
+                },
+                span,
+            ))
+        }
+        Rule::match_statement => {
+            let mut inner = pair.into_inner();
+            inner.next(); // kw_match
+            let scrutinee = build_expression(inner.next().expect("match has a scrutinee"))?;
+            let mut arms = Vec::new();
+            for arm in inner {
+                let mut arm_inner = arm.into_inner();
+                let pattern = build_match_pattern(arm_inner.next().expect("an arm has a pattern"))?;
+                let body = build_statement_list(arm_inner.next().expect("an arm has a body"))?;
+                arms.push(MatchArm { pattern, body });
+            }
+            Ok(Spanned::new(Statement::Match { scrutinee, arms }, span))
+        }
+        Rule::for_statement => {
+            let mut inner = pair.into_inner();
+            inner.next(); // kw_for
+            let variable = inner
+                .next()
+                .expect("for has a loop variable")
+                .as_str()
+                .to_string();
+            inner.next(); // in_op
+            let iterable = build_expression(inner.next().expect("for has an iterable"))?;
+            let body = build_statement_list(inner.next().expect("for has a body"))?;
+            Ok(Spanned::new(
+                Statement::ForIn {
+                    variable,
+                    iterable,
+                    body,
+                },
+                span,
+            ))
+        }
+        Rule::return_statement => {
+            let mut value = None;
+            for inner in pair.into_inner() {
+                if inner.as_rule() == Rule::expression {
+                    value = Some(build_expression(inner)?);
+                }
+            }
+            Ok(Spanned::new(Statement::Return { value }, span))
+        }
+        Rule::break_statement => Ok(Spanned::new(Statement::Break, span)),
+        Rule::continue_statement => Ok(Spanned::new(Statement::Continue, span)),
+        Rule::block_statement => {
+            let block = pair
+                .into_inner()
+                .next()
+                .expect("a block statement wraps a block");
+            Ok(Spanned::new(
+                Statement::Block(build_statement_list(block)?),
+                span,
+            ))
+        }
+        Rule::let_statement => {
+            let mut inner = pair.into_inner();
+            inner.next(); // kw_let
+            let name = inner
+                .next()
+                .expect("let has a name")
+                .as_str()
+                .to_string();
+            let mut type_annotation = None;
+            let mut value = None;
+            for part in inner {
+                match part.as_rule() {
+                    Rule::type_annotation => {
+                        let type_pair = part
+                            .into_inner()
+                            .next()
+                            .expect("an annotation wraps a type name");
+                        type_annotation = Some(match type_pair.as_str() {
+                            "int" => TypeName::Int,
+                            "float" => TypeName::Float,
+                            "string" => TypeName::String,
+                            "bool" => TypeName::Bool,
+                            other => {
+                                return Err(ParseError::new(
+                                    format!("unknown type name: {}", other),
+                                    span_of(&type_pair),
+                                ))
+                            }
+                        });
+                    }
+                    Rule::expression => value = Some(build_expression(part)?),
+                    _ => {}
+                }
+            }
+            let value = value.expect("let has a value");
+            Ok(Spanned::new(
+                Statement::Let {
+                    name,
+                    type_annotation,
+                    value,
+                },
+                span,
+            ))
+        }
+        Rule::index_assignment => {
+            let mut inner: Vec<_> = pair.into_inner().collect();
+            let value = build_expression(inner.pop().expect("index assignment has a value"))?;
+            let last_suffix = inner.pop().expect("index assignment has an index");
+            let index = build_expression(
+                last_suffix
+                    .into_inner()
+                    .next()
+                    .expect("an index suffix wraps an expression"),
+            )?;
+
+            // The identifier plus all but the last suffix form the target,
+            // e.g. `a[0][1] = v` assigns into the element `a[0]`.
+            let mut parts = inner.into_iter();
+            let identifier = parts.next().expect("index assignment has a target");
+            let mut target = Spanned::new(
+                Expression::Variable(identifier.as_str().to_string()),
+                span_of(&identifier),
+            );
+            for suffix in parts {
+                let suffix_span = target.span.merge(span_of(&suffix));
+                let inner_index = build_expression(
+                    suffix
+                        .into_inner()
+                        .next()
+                        .expect("an index suffix wraps an expression"),
+                )?;
+                target = Spanned::new(
+                    Expression::Index {
+                        target: Box::new(target),
+                        index: Box::new(inner_index),
+                    },
+                    suffix_span,
+                );
+            }
+            Ok(Spanned::new(
+                Statement::IndexAssignment {
+                    target,
+                    index,
+                    value,
+                },
+                span,
+            ))
+        }
+        Rule::assignment => {
+            let mut inner = pair.into_inner();
+            let name = inner
+                .next()
+                .expect("assignment has a target")
+                .as_str()
+                .to_string();
+            let value = build_expression(inner.next().expect("assignment has a value"))?;
+            Ok(Spanned::new(Statement::Assignment { name, value }, span))
+        }
+        // `x += 1` becomes `x = x + 1`. The synthesized nodes reuse the
+        // original spans, and the whole statement is wrapped in `Desugared`
+        // so errors raised inside it can name what was written.
+        Rule::compound_assignment => {
+            let original = pair
+                .as_str()
+                .trim_end()
+                .trim_end_matches(';')
+                .trim_end()
+                .to_string();
+            let mut inner = pair.into_inner();
+            let name_pair = inner.next().expect("a compound assignment has a target");
+            let name = name_pair.as_str().to_string();
+            let name_span = span_of(&name_pair);
+            let operator = match inner
+                .next()
+                .expect("a compound assignment has an operator")
+                .as_str()
+            {
+                "+=" => BinaryOperator::Add,
+                "-=" => BinaryOperator::Subtract,
+                "*=" => BinaryOperator::Multiply,
+                "/=" => BinaryOperator::Divide,
+                other => {
+                    return Err(ParseError::new(
+                        format!("unknown compound operator: {}", other),
+                        span,
+                    ))
+                }
+            };
+            let value = build_expression(inner.next().expect("a compound assignment has a value"))?;
+            let binary_span = name_span.merge(value.span);
+            let binary = Spanned::new(
+                Expression::Binary {
+                    left: Box::new(Spanned::new(Expression::Variable(name.clone()), name_span)),
+                    operator,
+                    right: Box::new(value),
+                },
+                binary_span,
+            );
+            let assignment = Spanned::new(Statement::Assignment { name, value: binary }, span);
+            Ok(Spanned::new(
+                Statement::Desugared {
+                    original,
+                    statement: Box::new(assignment),
+                },
+                span,
+            ))
+        }
+        Rule::expression_statement => {
+            let expression = build_expression(
+                pair.into_inner()
+                    .next()
+                    .expect("an expression statement wraps an expression"),
+            )?;
+            Ok(Spanned::new(Statement::Expression(expression), span))
+        }
+        rule => Err(ParseError::new(
+            format!("unexpected rule in statement position: {:?}", rule),
+            span,
+        )),
+    }
+}
+
+fn build_if_statement(pair: Pair<Rule>) -> Result<Spanned<Statement>, ParseError> {
+    let span = span_of(&pair);
+    let mut inner = pair.into_inner();
+    inner.next(); // kw_if
+    let condition = build_expression(inner.next().expect("if has a condition"))?;
+    let then_branch = build_statement_list(inner.next().expect("if has a then branch"))?;
+    let else_branch = match inner.next() {
+        Some(clause) => {
+            let mut clause_inner = clause.into_inner();
+            clause_inner.next(); // kw_else
+            let branch = clause_inner.next().expect("else has a body");
+            match branch.as_rule() {
+                Rule::if_statement => Some(vec![build_if_statement(branch)?]),
+                Rule::block => Some(build_statement_list(branch)?),
+                rule => {
+                    return Err(ParseError::new(
+                        format!("unexpected rule in else position: {:?}", rule),
+                        span,
+                    ))
+                }
+            }
+        }
+        None => None,
+    };
+    Ok(Spanned::new(
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        },
+        span,
+    ))
+}
+
+fn build_statement_list(block: Pair<Rule>) -> Result<Vec<Spanned<Statement>>, ParseError> {
+    block.into_inner().map(build_statement).collect()
+}
+
+/// A match arm's pattern: a scalar literal, or `None` for the `_` default.
+fn build_match_pattern(pair: Pair<Rule>) -> Result<Option<Spanned<Expression>>, ParseError> {
+    let inner = pair
+        .into_inner()
+        .next()
+        .expect("a match pattern wraps exactly one alternative");
+    if inner.as_rule() == Rule::match_default {
+        return Ok(None);
+    }
+    build_literal(inner).map(Some)
+}
+
+fn build_expression(pair: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    match pair.as_rule() {
+        Rule::expression => build_expression(
+            pair.into_inner()
+                .next()
+                .expect("an expression wraps a precedence chain"),
+        ),
+        Rule::logical_or
+        | Rule::logical_and
+        | Rule::equality
+        | Rule::comparison
+        | Rule::term
+        | Rule::factor => build_binary_chain(pair),
+        Rule::unary => build_unary(pair),
+        Rule::postfix => build_postfix(pair),
+        Rule::primary => build_primary(pair),
+        rule => Err(ParseError::new(
+            format!("unexpected rule in expression position: {:?}", rule),
+            span_of(&pair),
+        )),
+    }
+}
+
+fn build_binary_chain(pair: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    let mut inner = pair.into_inner();
+    let mut left = build_expression(inner.next().expect("a chain has a first operand"))?;
+    while let Some(operator_pair) = inner.next() {
+        let operator = binary_operator(&operator_pair)?;
+        let right = build_expression(inner.next().expect("an operator has a right operand"))?;
+        let span = left.span.merge(right.span);
+        left = Spanned::new(
+            Expression::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+            },
+            span,
+        );
+    }
+    Ok(left)
+}
+
+fn binary_operator(pair: &Pair<Rule>) -> Result<BinaryOperator, ParseError> {
+    let operator = match pair.as_str() {
+        "+" => BinaryOperator::Add,
+        "-" => BinaryOperator::Subtract,
+        "*" => BinaryOperator::Multiply,
+        "/" => BinaryOperator::Divide,
+        "div" => BinaryOperator::FloorDivide,
+        "%" => BinaryOperator::Modulo,
+        "==" => BinaryOperator::Equal,
+        "!=" => BinaryOperator::NotEqual,
+        "<" => BinaryOperator::Less,
+        "<=" => BinaryOperator::LessEqual,
+        ">" => BinaryOperator::Greater,
+        ">=" => BinaryOperator::GreaterEqual,
+        "&&" | "and" => BinaryOperator::And,
+        "||" | "or" => BinaryOperator::Or,
+        "in" => BinaryOperator::In,
+        other => {
+            return Err(ParseError::new(
+                format!("unknown binary operator: {}", other),
+                span_of(pair),
+            ))
+        }
+    };
+    Ok(operator)
+}
+
+fn build_unary(pair: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    let mut inner = pair.into_inner();
+    let first = inner.next().expect("a unary has at least one child");
+    match first.as_rule() {
+        Rule::unary_op => {
+            let operator = match first.as_str() {
+                "-" => UnaryOperator::Negate,
+                "!" | "not" => UnaryOperator::Not,
+                other => {
+                    return Err(ParseError::new(
+                        format!("unknown unary operator: {}", other),
+                        span_of(&first),
+                    ))
+                }
+            };
+            let operand = build_expression(inner.next().expect("a unary operator has an operand"))?;
+            let span = span_of(&first).merge(operand.span);
+            Ok(Spanned::new(
+                Expression::Unary {
+                    operator,
+                    operand: Box::new(operand),
+                },
+                span,
+            ))
+        }
+        Rule::postfix => build_postfix(first),
+        rule => Err(ParseError::new(
+            format!("unexpected rule in unary position: {:?}", rule),
+            span_of(&first),
+        )),
+    }
+}
+
+fn build_postfix(pair: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    let mut inner = pair.into_inner();
+    let mut target = build_primary(inner.next().expect("a postfix has a primary"))?;
+    for suffix in inner {
+        let span = target.span.merge(span_of(&suffix));
+        target = match suffix.as_rule() {
+            Rule::index_suffix => {
+                let index = build_expression(
+                    suffix
+                        .into_inner()
+                        .next()
+                        .expect("an index suffix wraps an expression"),
+                )?;
+                Spanned::new(
+                    Expression::Index {
+                        target: Box::new(target),
+                        index: Box::new(index),
+                    },
+                    span,
+                )
+            }
+            Rule::method_suffix => {
+                let mut method_inner = suffix.into_inner();
+                let name = method_inner
+                    .next()
+                    .expect("a method suffix has a name")
+                    .as_str()
+                    .to_string();
+                let mut arguments = Vec::new();
+                if let Some(argument_list) = method_inner.next() {
+                    for argument in argument_list.into_inner() {
+                        arguments.push(build_expression(argument)?);
+                    }
+                }
+                Spanned::new(
+                    Expression::MethodCall {
+                        receiver: Box::new(target),
+                        name,
+                        arguments,
+                    },
+                    span,
+                )
+            }
+            other => unreachable!("unexpected postfix suffix: {:?}", other),
+        };
+    }
+    Ok(target)
+}
+
+/// Build one of the scalar literal rules — shared between `primary` and
+/// `match_pattern`, which admits exactly these.
+fn build_literal(inner: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    let span = span_of(&inner);
+    match inner.as_rule() {
+        Rule::null_literal => Ok(Spanned::new(Expression::Null, span)),
+        Rule::boolean_literal => Ok(Spanned::new(
+            Expression::Boolean(inner.as_str() == "true"),
+            span,
+        )),
+        Rule::integer_literal => {
+            let text = inner.as_str();
+            let value: i64 = text.parse().map_err(|error: std::num::ParseIntError| {
+                // The grammar only admits digit runs, so overflow is the one
+                // failure worth a specific message.
+                let message = match error.kind() {
+                    std::num::IntErrorKind::PosOverflow | std::num::IntErrorKind::NegOverflow => {
+                        "integer literal is too large for a 64-bit integer".to_string()
+                    }
+                    _ => format!("Invalid integer literal: {}", text),
+                };
+                ParseError::new(message, span)
+            })?;
+            Ok(Spanned::new(Expression::Integer(value), span))
+        }
+        Rule::float_literal => {
+            let text = inner.as_str();
+            let value: f64 = text
+                .parse()
+                .map_err(|_| ParseError::new(format!("Invalid float literal: {}", text), span))?;
+            Ok(Spanned::new(Expression::Float(value), span))
+        }
+        Rule::char_literal => {
+            let raw = inner
+                .into_inner()
+                .next()
+                .expect("a char literal wraps its contents")
+                .as_str();
+            let character = unescape_string(raw)
+                .chars()
+                .next()
+                .expect("the grammar requires one character unit");
+            Ok(Spanned::new(Expression::Char(character), span))
+        }
+        Rule::string_literal => {
+            let raw = inner
+                .into_inner()
+                .next()
+                .expect("a string literal wraps its contents")
+                .as_str();
+            Ok(Spanned::new(Expression::String(unescape_string(raw)), span))
+        }
+        rule => Err(ParseError::new(
+            format!("unexpected rule in literal position: {:?}", rule),
+            span,
+        )),
+    }
+}
+
+fn build_primary(pair: Pair<Rule>) -> Result<Spanned<Expression>, ParseError> {
+    let inner = pair
+        .into_inner()
+        .next()
+        .expect("a primary wraps exactly one alternative");
+    let span = span_of(&inner);
+    match inner.as_rule() {
+        Rule::null_literal
+        | Rule::boolean_literal
+        | Rule::integer_literal
+        | Rule::float_literal
+        | Rule::char_literal
+        | Rule::string_literal => build_literal(inner),
+        Rule::array_literal => {
+            let elements = inner
+                .into_inner()
+                .map(build_expression)
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(Spanned::new(Expression::Array(elements), span))
+        }
+        // `(a)` never reaches this rule — `parenthesized` is tried first —
+        // so any tuple pair with one element came from `(a,)`.
+        Rule::tuple => {
+            let elements = inner
+                .into_inner()
+                .map(build_expression)
+                .collect::<Result<Vec<_>, _>>()?;
+            if elements.is_empty() {
+                return Err(ParseError::new(
+                    "`()` is not an expression; write a value, or `(a,)` for a one-element tuple",
+                    span,
+                ));
+            }
+            Ok(Spanned::new(Expression::Tuple(elements), span))
+        }
+        Rule::map_literal => {
+            let mut entries = Vec::new();
+            for entry in inner.into_inner() {
+                let mut entry_inner = entry.into_inner();
+                let key_pair = entry_inner
+                    .next()
+                    .expect("a map entry has a key")
+                    .into_inner()
+                    .next()
+                    .expect("a map key wraps its alternative");
+                let key = match key_pair.as_rule() {
+                    Rule::string_literal => unescape_string(
+                        key_pair
+                            .into_inner()
+                            .next()
+                            .expect("a string literal wraps its contents")
+                            .as_str(),
+                    ),
+                    _ => key_pair.as_str().to_string(),
+                };
+                let value = build_expression(entry_inner.next().expect("a map entry has a value"))?;
+                entries.push((key, value));
+            }
+            Ok(Spanned::new(Expression::Map(entries), span))
+        }
+        Rule::function_call => {
+            let mut call_inner = inner.into_inner();
+            let name = call_inner
+                .next()
+                .expect("a call has a function name")
+                .as_str()
+                .to_string();
+            let mut arguments = Vec::new();
+            if let Some(argument_list) = call_inner.next() {
+                for argument in argument_list.into_inner() {
+                    arguments.push(build_expression(argument)?);
+                }
+            }
+            Ok(Spanned::new(
+                Expression::FunctionCall { name, arguments },
+                span,
+            ))
+        }
+        Rule::lambda => {
+            let mut parameters = Vec::new();
+            let mut body = Vec::new();
+            for part in inner.into_inner() {
+                match part.as_rule() {
+                    Rule::parameter_list => {
+                        parameters = part.into_inner().map(|p| p.as_str().to_string()).collect();
+                    }
+                    Rule::block => body = build_statement_list(part)?,
+                    _ => {}
+                }
+            }
+            Ok(Spanned::new(Expression::Lambda { parameters, body }, span))
+        }
+        Rule::variable => Ok(Spanned::new(
+            Expression::Variable(inner.as_str().to_string()),
+            span,
+        )),
+        Rule::parenthesized => build_expression(
+            inner
+                .into_inner()
+                .next()
+                .expect("parentheses wrap an expression"),
+        ),
+        rule => Err(ParseError::new(
+            format!("unexpected rule in primary position: {:?}", rule),
+            span,
+        )),
+    }
+}
+
+fn unescape_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut characters = text.chars();
+    while let Some(character) = characters.next() {
+        if character == '\\' {
+            match characters.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some(other) => result.push(other),
+                None => {}
+            }
+        } else {
+            result.push(character);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_assignment() {
+        let program = parse_program("x = 5;").unwrap();
+        assert_eq!(program.statements.len(), 1);
+        match &program.statements[0].value {
+            Statement::Assignment { name, value } => {
+                assert_eq!(name, "x");
+                assert_eq!(value.value, Expression::Integer(5));
+            }
+            other => panic!("expected an assignment, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_precedence() {
+        let expression = parse_expression("1 + 2 * 3").unwrap();
+        match expression.value {
+            Expression::Binary {
+                left,
+                operator: BinaryOperator::Add,
+                right,
+            } => {
+                assert_eq!(left.value, Expression::Integer(1));
+                assert!(matches!(
+                    right.value,
+                    Expression::Binary {
+                        operator: BinaryOperator::Multiply,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected addition at the top, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_function_definition() {
+        let program = parse_program("def add(a, b) { return a + b; }").unwrap();
+        match &program.statements[0].value {
+            Statement::FunctionDefinition {
+                name,
+                parameters,
+                body,
+                doc,
+            } => {
+                assert_eq!(name, "add");
+                assert_eq!(parameters, &["a".to_string(), "b".to_string()]);
+                assert_eq!(body.len(), 1);
+                assert_eq!(doc, &None);
+            }
+            other => panic!("expected a function definition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn compound_assignment_desugars_and_keeps_the_original_text() {
+        let program = parse_program("x = 1; x += 2;").unwrap();
+        match &program.statements[1].value {
+            Statement::Desugared {
+                original,
+                statement,
+            } => {
+                assert_eq!(original, "x += 2");
+                assert_eq!(statement.value.to_sexpr(), "(assign x (+ x 2))");
+            }
+            other => panic!("expected a desugared statement, got {:?}", other),
+        }
+        // `to_sexpr` is transparent about the wrapper.
+        assert_eq!(program.statements[1].value.to_sexpr(), "(assign x (+ x 2))");
+    }
+
+    #[test]
+    fn each_compound_operator_desugars_to_its_binary_form() {
+        let program = parse_program("x -= 1; x *= 2; x /= 3;").unwrap();
+        let rendered: Vec<String> = program
+            .statements
+            .iter()
+            .map(|statement| statement.value.to_sexpr())
+            .collect();
+        assert_eq!(
+            rendered,
+            ["(assign x (- x 1))", "(assign x (* x 2))", "(assign x (/ x 3))"]
+        );
+    }
+
+    #[test]
+    fn condition_parentheses_are_optional() {
+        let with_parens = parse_program("if (x > 0) { print(1); } else { print(2); }").unwrap();
+        let without = parse_program("if x > 0 { print(1); } else { print(2); }").unwrap();
+        assert_eq!(
+            with_parens.statements[0].value.to_sexpr(),
+            without.statements[0].value.to_sexpr()
+        );
+    }
+
+    #[test]
+    fn a_bare_while_condition_parses_like_the_parenthesized_form() {
+        let with_parens = parse_program("while (x < 10) { x = x + 1; }").unwrap();
+        let without = parse_program("while x < 10 { x = x + 1; }").unwrap();
+        assert_eq!(
+            with_parens.statements[0].value.to_sexpr(),
+            without.statements[0].value.to_sexpr()
+        );
+    }
+
+    #[test]
+    fn match_statement_parses_with_literal_and_default_arms() {
+        let program = parse_program(
+            "match x { 1 => { print(1); } \"two\" => { print(2); } _ => { print(3); } }",
+        )
+        .unwrap();
+        assert_eq!(
+            program.statements[0].value.to_sexpr(),
+            "(match x (arm 1 (call print 1)) (arm \"two\" (call print 2)) (arm _ (call print 3)))"
+        );
+    }
+
+    #[test]
+    fn match_is_a_reserved_word() {
+        let error = parse_program("match = 5;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "`match` is a keyword and cannot be used as an identifier"
+        );
+    }
+
+    #[test]
+    fn over_deep_bracket_nesting_is_refused_before_parsing() {
+        let source = format!("x = {}1{};", "(".repeat(40), ")".repeat(40));
+        let error = parse_program(&source).unwrap_err();
+        assert_eq!(error.message, "brackets nested deeper than 32 levels");
+        // The span points at the bracket that crossed the limit.
+        let span = error.span;
+        assert_eq!(&source[span.start..span.end], "(");
+    }
+
+    #[test]
+    fn brackets_inside_strings_and_comments_do_not_count() {
+        let source = format!(
+            "// {}\nx = \"{}\";",
+            "(".repeat(40),
+            "[".repeat(40)
+        );
+        parse_program(&source).unwrap();
+    }
+
+    #[test]
+    fn recovery_reports_over_deep_nesting_as_its_one_error() {
+        let source = format!("x = {}1{};", "{".repeat(40), "}".repeat(40));
+        let (program, errors) = parse_program_with_recovery(&source);
+        assert!(program.statements.is_empty());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "brackets nested deeper than 32 levels");
+    }
+
+    #[test]
+    fn a_missing_operand_recovers_as_a_placeholder_with_one_error() {
+        let (expression, errors) = parse_expression_with_recovery("1 + ");
+        assert_eq!(errors.len(), 1);
+        let expression = expression.expect("the binary tree should survive");
+        let Expression::Binary { left, right, .. } = &expression.value else {
+            panic!("expected a binary expression, got {:?}", expression.value);
+        };
+        assert!(matches!(left.value, Expression::Integer(1)));
+        // The placeholder is a null literal with an empty span at the hole.
+        assert!(matches!(right.value, Expression::Null));
+        assert_eq!(right.span.start, right.span.end);
+        assert_eq!(&"1 + "[expression.span.start..expression.span.end], "1 + ");
+    }
+
+    #[test]
+    fn clean_input_recovers_without_errors() {
+        let (expression, errors) = parse_expression_with_recovery("1 + 2");
+        assert!(errors.is_empty());
+        assert!(expression.is_some());
+    }
+
+    #[test]
+    fn hopeless_input_returns_no_tree_but_every_error() {
+        let (expression, errors) = parse_expression_with_recovery(")(");
+        assert!(expression.is_none());
+        assert!(!errors.is_empty());
+    }
+
+    #[test]
+    fn a_statement_recovers_a_missing_operand_and_semicolon() {
+        let (statement, errors) = parse_statement_with_recovery("x = 1 +");
+        assert_eq!(errors.len(), 2);
+        let statement = statement.expect("the assignment should survive");
+        let Statement::Assignment { name, value } = &statement.value else {
+            panic!("expected an assignment, got {:?}", statement.value);
+        };
+        assert_eq!(name, "x");
+        let Expression::Binary { right, .. } = &value.value else {
+            panic!("expected a binary value, got {:?}", value.value);
+        };
+        assert!(matches!(right.value, Expression::Null));
+        assert_eq!(right.span.start, right.span.end);
+    }
+
+    #[test]
+    fn a_brace_after_a_complete_condition_opens_the_block() {
+        // `flag` ends the condition; the `{` is the block, not a map literal.
+        let program = parse_program("if flag { x = 1; }").unwrap();
+        match &program.statements[0].value {
+            Statement::If { condition, .. } => {
+                assert_eq!(condition.value, Expression::Variable("flag".into()));
+            }
+            other => panic!("expected an if, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_trailing_return_may_drop_its_semicolon() {
+        let program = parse_program("def f(x) { return x }").unwrap();
+        match &program.statements[0].value {
+            Statement::FunctionDefinition { body, .. } => {
+                assert_eq!(body[0].value.to_sexpr(), "(return x)");
+            }
+            other => panic!("expected a function definition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn a_trailing_bare_return_may_drop_its_semicolon() {
+        assert!(parse_program("def f(x) { print(x); return }").is_ok());
+    }
+
+    #[test]
+    fn a_return_before_another_statement_still_needs_its_semicolon() {
+        assert!(parse_program("def f(x) { return x print(x); }").is_err());
+    }
+
+    #[test]
+    fn doc_comment_is_attached_to_function() {
+        let source = "/// Adds two numbers.\n/// Really.\ndef add(a, b) { return a + b; }";
+        let program = parse_program(source).unwrap();
+        match &program.statements[0].value {
+            Statement::FunctionDefinition { doc, .. } => {
+                assert_eq!(doc.as_deref(), Some("Adds two numbers.\nReally."));
+            }
+            other => panic!("expected a function definition, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn plain_comments_are_still_skipped() {
+        let program = parse_program("// just a note\nx = 1;").unwrap();
+        assert_eq!(program.statements.len(), 1);
+    }
+
+    #[test]
+    fn parse_if_else_chain() {
+        let program = parse_program("if (a) { x = 1; } else if (b) { x = 2; } else { x = 3; }")
+            .unwrap();
+        match &program.statements[0].value {
+            Statement::If { else_branch, .. } => {
+                let else_branch = else_branch.as_ref().expect("an else branch");
+                assert!(matches!(
+                    else_branch[0].value,
+                    Statement::If {
+                        else_branch: Some(_),
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected an if statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn word_operators_match_their_symbolic_forms() {
+        // Compare S-expressions: the ASTs are identical up to spans, which
+        // shift with the operators' widths.
+        for (word, symbolic) in [
+            ("a and b", "a && b"),
+            ("a or b", "a || b"),
+            ("not a", "!a"),
+        ] {
+            assert_eq!(
+                parse_expression(word).unwrap().value.to_sexpr(),
+                parse_expression(symbolic).unwrap().value.to_sexpr()
+            );
+        }
+    }
+
+    #[test]
+    fn print_statement_slip_gets_a_suggestion() {
+        let error = parse_program("print 5;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "`print` is a function: write `print(...)`, not `print ...;`"
+        );
+    }
+
+    #[test]
+    fn parse_error_boxes_into_dyn_error() {
+        let error = parse_program("def").unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert!(boxed.to_string().contains(" at "));
+    }
+
+    #[test]
+    fn implicit_semicolons_terminate_simple_statements() {
+        let program =
+            parse_program_with_implicit_semicolons("x = 1\ny = x + 2\nprint(y)\n").unwrap();
+        assert_eq!(program.statements.len(), 3);
+    }
+
+    #[test]
+    fn implicit_semicolons_work_inside_blocks() {
+        let source = "def f(x) {\n    y = x + 1\n    return y\n}\nprint(f(1))\n";
+        let program = parse_program_with_implicit_semicolons(source).unwrap();
+        assert_eq!(program.statements.len(), 2);
+    }
+
+    #[test]
+    fn implicit_semicolons_leave_multiline_expressions_alone() {
+        let source = "x = (1 +\n     2)\nitems = [\n    1,\n    2,\n]\n";
+        let program = parse_program_with_implicit_semicolons(source).unwrap();
+        assert_eq!(program.statements.len(), 2);
+    }
+
+    #[test]
+    fn implicit_semicolons_respect_trailing_word_operators() {
+        let source = "x = a and\n    b\n";
+        let program = parse_program_with_implicit_semicolons(source).unwrap();
+        assert_eq!(program.statements.len(), 1);
+    }
+
+    #[test]
+    fn explicit_semicolons_still_work_in_implicit_mode() {
+        let program = parse_program_with_implicit_semicolons("x = 1; y = 2\n").unwrap();
+        assert_eq!(program.statements.len(), 2);
+    }
+
+    #[test]
+    fn empty_bodies_parse_everywhere() {
+        let program = parse_program("def f() {} if (x) {} while (x) {} {}").unwrap();
+        assert_eq!(program.statements.len(), 4);
+        match &program.statements[0].value {
+            Statement::FunctionDefinition { body, .. } => assert!(body.is_empty()),
+            other => panic!("expected a function definition, got {:?}", other),
+        }
+        match &program.statements[1].value {
+            Statement::If {
+                then_branch,
+                else_branch,
+                ..
+            } => {
+                assert!(then_branch.is_empty());
+                assert!(else_branch.is_none());
+            }
+            other => panic!("expected an if statement, got {:?}", other),
+        }
+        match &program.statements[2].value {
+            Statement::While { body, .. } => assert!(body.is_empty()),
+            other => panic!("expected a while statement, got {:?}", other),
+        }
+        match &program.statements[3].value {
+            Statement::Block(body) => assert!(body.is_empty()),
+            other => panic!("expected a block, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_let_with_and_without_annotation() {
+        let program = parse_program("let x: int = 5; let y = 2;").unwrap();
+        match &program.statements[0].value {
+            Statement::Let {
+                name,
+                type_annotation,
+                value,
+            } => {
+                assert_eq!(name, "x");
+                assert_eq!(type_annotation, &Some(TypeName::Int));
+                assert_eq!(value.value, Expression::Integer(5));
+            }
+            other => panic!("expected a let statement, got {:?}", other),
+        }
+        match &program.statements[1].value {
+            Statement::Let {
+                type_annotation, ..
+            } => assert_eq!(type_annotation, &None),
+            other => panic!("expected a let statement, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn an_unknown_type_name_is_a_parse_error() {
+        assert!(parse_program("let x: widget = 5;").is_err());
+    }
+
+    #[test]
+    fn parse_method_calls() {
+        assert_eq!(
+            parse_expression("\"abc\".len()").unwrap().value.to_sexpr(),
+            "(method len \"abc\")"
+        );
+        assert_eq!(
+            parse_expression("arr.push(x, 1)").unwrap().value.to_sexpr(),
+            "(method push arr x 1)"
+        );
+        // Suffixes chain left to right, mixing with indexing.
+        assert_eq!(
+            parse_expression("rows[0].map(f)").unwrap().value.to_sexpr(),
+            "(method map (index rows 0) f)"
+        );
+    }
+
+    #[test]
+    fn a_method_dot_does_not_eat_float_literals() {
+        assert_eq!(
+            parse_expression("1.5.round(0)").unwrap().value.to_sexpr(),
+            "(method round 1.5 0)"
+        );
+    }
+
+    #[test]
+    fn parse_lambda_expression() {
+        let expression = parse_expression("fn(a, b) { return a + b; }").unwrap();
+        match expression.value {
+            Expression::Lambda { parameters, body } => {
+                assert_eq!(parameters, ["a".to_string(), "b".to_string()]);
+                assert_eq!(body.len(), 1);
+            }
+            other => panic!("expected a lambda, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_exponent_and_leading_point_floats() {
+        for (source, expected) in [("1e10", 1e10), ("2.5e-3", 2.5e-3), (".5", 0.5), ("3E+2", 3e2)]
+        {
+            let expression = parse_expression(source).unwrap();
+            assert_eq!(expression.value, Expression::Float(expected), "for {}", source);
+        }
+    }
+
+    #[test]
+    fn a_trailing_point_is_not_a_float() {
+        // `5.` stays an integer followed by a stray `.`, which cannot parse.
+        assert!(parse_program("x = 5.;").is_err());
+    }
+
+    #[test]
+    fn parse_char_literal() {
+        let expression = parse_expression("'a'").unwrap();
+        assert_eq!(expression.value, Expression::Char('a'));
+    }
+
+    #[test]
+    fn parse_char_escape() {
+        let expression = parse_expression(r"'\n'").unwrap();
+        assert_eq!(expression.value, Expression::Char('\n'));
+    }
+
+    #[test]
+    fn word_operators_do_not_claim_identifier_prefixes() {
+        let expression = parse_expression("android").unwrap();
+        assert_eq!(
+            expression.value,
+            Expression::Variable("android".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_string_escapes() {
+        let expression = parse_expression(r#""a\nb""#).unwrap();
+        assert_eq!(expression.value, Expression::String("a\nb".to_string()));
+    }
+
+    #[test]
+    fn recovery_keeps_statements_around_an_error() {
+        let (program, errors) = parse_program_with_recovery("a = 1; b = ; c = 3;");
+        assert_eq!(program.statements.len(), 2);
+        assert!(matches!(
+            &program.statements[0].value,
+            Statement::Assignment { name, .. } if name == "a"
+        ));
+        assert!(matches!(
+            &program.statements[1].value,
+            Statement::Assignment { name, .. } if name == "c"
+        ));
+        assert_eq!(errors.len(), 1);
+        // The recovered statement's spans point into the original source.
+        assert_eq!(program.statements[1].span.start, 13);
+    }
+
+    #[test]
+    fn recovery_on_a_clean_file_reports_no_errors() {
+        let (program, errors) = parse_program_with_recovery("x = 1; y = 2;");
+        assert_eq!(program.statements.len(), 2);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn unexpected_rule_under_program_is_an_error() {
+        // Feed `build_program` a pair that is not a program: its inner rules
+        // are not in the allowlist, so it must refuse rather than skip them.
+        let pair = AmarokParser::parse(Rule::expression, "1 + 1")
+            .unwrap()
+            .next()
+            .unwrap();
+        let error = build_program(pair).unwrap_err();
+        assert_eq!(error.message, "unexpected rule under program: logical_or");
+    }
+
+    #[test]
+    fn overflowing_integer_literal_has_a_specific_error() {
+        let error = parse_expression("99999999999999999999999").unwrap_err();
+        assert_eq!(
+            error.message,
+            "integer literal is too large for a 64-bit integer"
+        );
+        assert_eq!(error.span, Span::new(0, 23));
+    }
+
+    #[test]
+    fn parse_for_in() {
+        let program = parse_program("for x in arr { print(x); }").unwrap();
+        assert_eq!(
+            program.statements[0].value.to_sexpr(),
+            "(for x arr (call print x))"
+        );
+    }
+
+    #[test]
+    fn parentheses_group_and_commas_make_tuples() {
+        assert_eq!(parse_expression("(1)").unwrap().value.to_sexpr(), "1");
+        assert_eq!(
+            parse_expression("(1,)").unwrap().value.to_sexpr(),
+            "(tuple 1)"
+        );
+        assert_eq!(
+            parse_expression("(1, 2)").unwrap().value.to_sexpr(),
+            "(tuple 1 2)"
+        );
+    }
+
+    #[test]
+    fn empty_parentheses_are_rejected_with_a_hint() {
+        let error = parse_expression("()").unwrap_err();
+        assert_eq!(
+            error.message,
+            "`()` is not an expression; write a value, or `(a,)` for a one-element tuple"
+        );
+    }
+
+    #[test]
+    fn parse_expression_consumes_the_whole_input() {
+        let expression = parse_expression("1 + 2").unwrap();
+        assert_eq!(expression.value.to_sexpr(), "(+ 1 2)");
+    }
+
+    #[test]
+    fn statement_syntax_is_not_an_expression() {
+        let error = parse_expression("x = 1;").unwrap_err();
+        assert_eq!(error.message, "expected an expression, but found a statement");
+        assert_eq!(error.span, Span::new(0, 6));
+    }
+
+    #[test]
+    fn parse_error_has_span() {
+        let error = parse_program("x = ;").unwrap_err();
+        assert_eq!(error.span.start, 4);
+    }
+
+    #[test]
+    fn a_keyword_cannot_be_an_assignment_target() {
+        let error = parse_program("if = 5;").unwrap_err();
+        assert_eq!(
+            error.message,
+            "`if` is a keyword and cannot be used as an identifier"
+        );
+    }
+
+    #[test]
+    fn a_keyword_cannot_name_a_function() {
+        let error = parse_program("def if() {}").unwrap_err();
+        assert_eq!(
+            error.message,
+            "`if` is a keyword and cannot be used as an identifier"
+        );
+    }
+
+    #[test]
+    fn an_assignment_in_a_condition_suggests_equality() {
+        let error = parse_program("if (x = 5) {}").unwrap_err();
+        assert_eq!(
+            error.message,
+            "assignment is not allowed in a condition; did you mean `==`?"
+        );
+    }
+
+    #[test]
+    fn a_bare_while_condition_assignment_gets_the_same_suggestion() {
+        let error = parse_program("while x = 5 { x = 0; }").unwrap_err();
+        assert_eq!(
+            error.message,
+            "assignment is not allowed in a condition; did you mean `==`?"
+        );
+    }
+
+    #[test]
+    fn a_comparison_condition_still_parses() {
+        assert!(parse_program("if (x == 5) {}").is_ok());
+    }
+
+    #[test]
+    fn keyword_prefixes_are_still_identifiers() {
+        let program = parse_program("iffy = 1; android = 2; forever = 3;").unwrap();
+        assert_eq!(program.statements.len(), 3);
+    }
+
+    #[test]
+    fn a_shebang_line_is_skipped() {
+        let program = parse_program("#!/usr/bin/env amarok\nprint(1);").unwrap();
+        assert_eq!(program.statements[0].value.to_sexpr(), "(call print 1)");
+        // The span still indexes the original file, past the shebang.
+        assert_eq!(program.statements[0].span, Span::new(22, 31));
+    }
+
+    #[test]
+    fn an_error_after_a_shebang_keeps_its_offset() {
+        // Same program as `parse_error_has_span`, shifted by the 22-byte
+        // shebang line: the error still points into the original file.
+        let error = parse_program("#!/usr/bin/env amarok\nx = ;").unwrap_err();
+        assert_eq!(error.span.start, 26);
+    }
+
+    #[test]
+    fn binary_span_covers_both_operands() {
+        let expression = parse_expression("a + b").unwrap();
+        assert_eq!(expression.span, Span::new(0, 5));
+    }
+}
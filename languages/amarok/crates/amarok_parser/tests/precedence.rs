@@ -0,0 +1,82 @@
+//! Pins the relative precedence and associativity of every operator by
+//! comparing mixed expressions against their explicitly parenthesized
+//! forms. Spans differ between the two spellings, so shapes are compared as
+//! S-expressions. Grow this suite whenever the grammar gains an operator.
+
+use amarok_parser::parse_expression;
+
+/// Assert that `mixed` parses to the same shape as `parenthesized`.
+fn assert_same_shape(mixed: &str, parenthesized: &str) {
+    let mixed_shape = parse_expression(mixed).unwrap().value.to_sexpr();
+    let explicit_shape = parse_expression(parenthesized).unwrap().value.to_sexpr();
+    assert_eq!(
+        mixed_shape, explicit_shape,
+        "{:?} should parse like {:?}",
+        mixed, parenthesized
+    );
+}
+
+#[test]
+fn multiplication_binds_tighter_than_addition() {
+    assert_same_shape("1 + 2 * 3", "1 + (2 * 3)");
+    assert_same_shape("1 * 2 + 3 * 4", "(1 * 2) + (3 * 4)");
+}
+
+#[test]
+fn division_and_modulo_share_a_level_left_associatively() {
+    assert_same_shape("10 / 2 % 3", "(10 / 2) % 3");
+    assert_same_shape("10 % 3 / 2", "(10 % 3) / 2");
+}
+
+#[test]
+fn subtraction_is_left_associative() {
+    assert_same_shape("1 - 2 - 3", "(1 - 2) - 3");
+}
+
+#[test]
+fn unary_binds_tighter_than_arithmetic() {
+    assert_same_shape("-a * b", "(-a) * b");
+    assert_same_shape("-a + b", "(-a) + b");
+}
+
+#[test]
+fn indexing_binds_tighter_than_unary() {
+    assert_same_shape("-xs[0]", "-(xs[0])");
+}
+
+#[test]
+fn arithmetic_binds_tighter_than_comparison() {
+    assert_same_shape("a + b < c + d", "(a + b) < (c + d)");
+}
+
+#[test]
+fn membership_sits_at_the_comparison_level() {
+    assert_same_shape("a + b in c", "(a + b) in c");
+    assert_same_shape("x in xs and y in ys", "(x in xs) and (y in ys)");
+}
+
+#[test]
+fn comparison_binds_tighter_than_equality() {
+    assert_same_shape("a < b == c < d", "(a < b) == (c < d)");
+}
+
+#[test]
+fn equality_binds_tighter_than_and() {
+    assert_same_shape("a == b and c != d", "(a == b) and (c != d)");
+}
+
+#[test]
+fn not_binds_tighter_than_and_and_or() {
+    assert_same_shape("!a and b", "(!a) and b");
+    assert_same_shape("not a or b", "(not a) or b");
+}
+
+#[test]
+fn and_binds_tighter_than_or() {
+    assert_same_shape("a and b or c and d", "(a and b) or (c and d)");
+}
+
+#[test]
+fn calls_nest_inside_arithmetic() {
+    assert_same_shape("a * f(b) + c", "(a * f(b)) + c");
+}
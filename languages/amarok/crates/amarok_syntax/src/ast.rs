@@ -0,0 +1,1036 @@
+//! The abstract syntax tree for Amarok programs.
+
+use std::fmt;
+
+use crate::Spanned;
+
+/// A parsed source file: a flat list of top-level statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Program {
+    pub statements: Vec<Spanned<Statement>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Statement {
+    /// `name = value;` — assigns to an existing variable or defines a new one.
+    Assignment {
+        name: String,
+        value: Spanned<Expression>,
+    },
+    /// `let name: type = value;` — declares a binding in the current scope.
+    /// The optional annotation is checked against the initializer's runtime
+    /// type, a stepping stone toward a static checker.
+    Let {
+        name: String,
+        type_annotation: Option<TypeName>,
+        value: Spanned<Expression>,
+    },
+    /// `target[index] = value;` — mutates an array element or map entry in
+    /// place.
+    IndexAssignment {
+        target: Spanned<Expression>,
+        index: Spanned<Expression>,
+        value: Spanned<Expression>,
+    },
+    /// A statement synthesized by a desugaring — today, compound assignment:
+    /// `x += 1` becomes `x = x + 1`. The source text as written rides along
+    /// so a diagnostic raised inside the synthesized node can say what the
+    /// user actually wrote.
+    Desugared {
+        /// The original syntax, e.g. `x += 1` (no trailing `;`).
+        original: String,
+        statement: Box<Spanned<Statement>>,
+    },
+    /// `def name(parameters) { body }`
+    FunctionDefinition {
+        name: String,
+        parameters: Vec<String>,
+        body: Vec<Spanned<Statement>>,
+        /// The `///` doc comment immediately preceding the definition, with
+        /// comment markers stripped and lines joined by `\n`.
+        doc: Option<String>,
+    },
+    If {
+        condition: Spanned<Expression>,
+        then_branch: Vec<Spanned<Statement>>,
+        /// An `else if` chain is represented as an else branch containing a
+        /// single nested `If` statement.
+        else_branch: Option<Vec<Spanned<Statement>>>,
+    },
+    /// `match scrutinee { 1 => { ... } "two" => { ... } _ => { ... } }` —
+    /// multi-way dispatch. Each pattern is a literal compared to the
+    /// scrutinee with value equality; the first matching arm's block runs.
+    /// No matching arm and no `_` default is a no-op, like an `if` without
+    /// an `else`.
+    Match {
+        scrutinee: Spanned<Expression>,
+        arms: Vec<MatchArm>,
+    },
+    While {
+        condition: Spanned<Expression>,
+        body: Vec<Spanned<Statement>>,
+        /// Runs only when the condition was false on the very first check —
+        /// i.e. the body executed zero times.
+        else_branch: Option<Vec<Spanned<Statement>>>,
+    },
+    /// `for variable in iterable { body }` — iterates an array's elements, a
+    /// string's characters, or a map's keys, binding the variable afresh each
+    /// pass.
+    ForIn {
+        variable: String,
+        iterable: Spanned<Expression>,
+        body: Vec<Spanned<Statement>>,
+    },
+    Return {
+        value: Option<Spanned<Expression>>,
+    },
+    Break,
+    Continue,
+    /// A bare `{ ... }` block introducing a new scope.
+    Block(Vec<Spanned<Statement>>),
+    /// An expression evaluated for its side effects, e.g. `print(x);`.
+    Expression(Spanned<Expression>),
+}
+
+/// One `pattern => { body }` arm of a [`Statement::Match`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    /// The literal to compare against, or `None` for the `_` default.
+    pub pattern: Option<Spanned<Expression>>,
+    pub body: Vec<Spanned<Statement>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Null,
+    Integer(i64),
+    /// `1.5`, `.5`, `1e10`, `2.5e-3` — a 64-bit float. A trailing point
+    /// (`5.`) is not accepted.
+    Float(f64),
+    Boolean(bool),
+    /// `'a'` — a single Unicode scalar value.
+    Char(char),
+    String(String),
+    Variable(String),
+    /// `[a, b, c]`
+    Array(Vec<Spanned<Expression>>),
+    /// `{key: value, "other key": value}` — keys are strings either way.
+    Map(Vec<(String, Spanned<Expression>)>),
+    /// `(a,)` or `(a, b)` — a comma distinguishes a tuple from grouping, so
+    /// `(a)` is just `a`. The runtime has no distinct tuple type yet; a
+    /// tuple evaluates to an array.
+    Tuple(Vec<Spanned<Expression>>),
+    /// `target[index]`
+    Index {
+        target: Box<Spanned<Expression>>,
+        index: Box<Spanned<Expression>>,
+    },
+    Unary {
+        operator: UnaryOperator,
+        operand: Box<Spanned<Expression>>,
+    },
+    Binary {
+        left: Box<Spanned<Expression>>,
+        operator: BinaryOperator,
+        right: Box<Spanned<Expression>>,
+    },
+    FunctionCall {
+        name: String,
+        arguments: Vec<Spanned<Expression>>,
+    },
+    /// `receiver.name(arguments)` — method-call sugar. The interpreter runs
+    /// it as `name(receiver, arguments)`, so any function taking its subject
+    /// first is callable in either style.
+    MethodCall {
+        receiver: Box<Spanned<Expression>>,
+        name: String,
+        arguments: Vec<Spanned<Expression>>,
+    },
+    /// `fn(a, b) { return a + b; }` — an anonymous function expression. It
+    /// evaluates to a first-class function value that captures the bindings
+    /// visible where it was written.
+    Lambda {
+        parameters: Vec<String>,
+        body: Vec<Spanned<Statement>>,
+    },
+}
+
+/// A type name usable in a `let` annotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypeName {
+    Int,
+    Float,
+    String,
+    Bool,
+}
+
+impl TypeName {
+    /// The name as it appears in source code.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Int => "int",
+            Self::Float => "float",
+            Self::String => "string",
+            Self::Bool => "bool",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    /// `/` — on two integers this stays truncating integer division; floats
+    /// divide exactly.
+    Divide,
+    /// Floor division, always rounding toward negative infinity. Written as
+    /// the word `div` (after `and`/`or`/`not`), because `//` opens a line
+    /// comment.
+    FloorDivide,
+    Modulo,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+    /// Membership: element of an array, key of a map, substring of a string.
+    In,
+}
+
+impl BinaryOperator {
+    /// The operator as it appears in source code.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::FloorDivide => "div",
+            Self::Modulo => "%",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::Less => "<",
+            Self::LessEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterEqual => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+            Self::In => "in",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Negate,
+    Not,
+}
+
+impl UnaryOperator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Negate => "-",
+            Self::Not => "!",
+        }
+    }
+}
+
+impl Program {
+    /// The whole program as S-expressions, one statement per line.
+    pub fn to_sexpr(&self) -> String {
+        self.statements
+            .iter()
+            .map(|statement| statement.value.to_sexpr())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Clone the program without recursing through expression nesting.
+    ///
+    /// See [`deep_clone_expression`] for why this exists.
+    pub fn deep_clone(&self) -> Program {
+        Program {
+            statements: deep_clone_statements(&self.statements),
+        }
+    }
+}
+
+impl Statement {
+    /// A compact S-expression form, e.g. `(assign x (+ 1 2))`. Spans are
+    /// omitted; the output is meant for tooling and diffing.
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            Statement::Assignment { name, value } => {
+                format!("(assign {} {})", name, value.value.to_sexpr())
+            }
+            // Transparent: tooling sees the desugared form.
+            Statement::Desugared { statement, .. } => statement.value.to_sexpr(),
+            Statement::Let {
+                name,
+                type_annotation,
+                value,
+            } => match type_annotation {
+                Some(annotation) => format!(
+                    "(let {}:{} {})",
+                    name,
+                    annotation.name(),
+                    value.value.to_sexpr()
+                ),
+                None => format!("(let {} {})", name, value.value.to_sexpr()),
+            },
+            Statement::IndexAssignment {
+                target,
+                index,
+                value,
+            } => format!(
+                "(index-assign {} {} {})",
+                target.value.to_sexpr(),
+                index.value.to_sexpr(),
+                value.value.to_sexpr()
+            ),
+            Statement::FunctionDefinition {
+                name,
+                parameters,
+                body,
+                ..
+            } => format!(
+                "(def {} ({}){})",
+                name,
+                parameters.join(" "),
+                sexpr_body(body)
+            ),
+            Statement::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                let mut rendered = format!(
+                    "(if {} (then{})",
+                    condition.value.to_sexpr(),
+                    sexpr_body(then_branch)
+                );
+                if let Some(else_branch) = else_branch {
+                    rendered.push_str(&format!(" (else{})", sexpr_body(else_branch)));
+                }
+                rendered.push(')');
+                rendered
+            }
+            Statement::Match { scrutinee, arms } => {
+                let rendered: String = arms
+                    .iter()
+                    .map(|arm| {
+                        let pattern = match &arm.pattern {
+                            Some(pattern) => pattern.value.to_sexpr(),
+                            None => "_".to_string(),
+                        };
+                        format!(" (arm {}{})", pattern, sexpr_body(&arm.body))
+                    })
+                    .collect();
+                format!("(match {}{})", scrutinee.value.to_sexpr(), rendered)
+            }
+            Statement::While {
+                condition,
+                body,
+                else_branch,
+            } => {
+                let mut rendered =
+                    format!("(while {}{}", condition.value.to_sexpr(), sexpr_body(body));
+                if let Some(else_branch) = else_branch {
+                    rendered.push_str(&format!(" (else{})", sexpr_body(else_branch)));
+                }
+                rendered.push(')');
+                rendered
+            }
+            Statement::ForIn {
+                variable,
+                iterable,
+                body,
+            } => format!(
+                "(for {} {}{})",
+                variable,
+                iterable.value.to_sexpr(),
+                sexpr_body(body)
+            ),
+            Statement::Return { value } => match value {
+                Some(value) => format!("(return {})", value.value.to_sexpr()),
+                None => "(return)".to_string(),
+            },
+            Statement::Break => "(break)".to_string(),
+            Statement::Continue => "(continue)".to_string(),
+            Statement::Block(statements) => format!("(block{})", sexpr_body(statements)),
+            Statement::Expression(expression) => expression.value.to_sexpr(),
+        }
+    }
+}
+
+fn sexpr_body(statements: &[Spanned<Statement>]) -> String {
+    statements
+        .iter()
+        .map(|statement| format!(" {}", statement.value.to_sexpr()))
+        .collect()
+}
+
+impl Expression {
+    /// A compact S-expression form; see [`Statement::to_sexpr`].
+    pub fn to_sexpr(&self) -> String {
+        match self {
+            Expression::Null => "null".to_string(),
+            Expression::Integer(value) => value.to_string(),
+            Expression::Float(value) => format!("{:?}", value),
+            Expression::Boolean(value) => value.to_string(),
+            Expression::Char(value) => format!("{:?}", value),
+            Expression::String(value) => format!("{:?}", value),
+            Expression::Variable(name) => name.clone(),
+            Expression::Array(elements) => {
+                let rendered: String = elements
+                    .iter()
+                    .map(|element| format!(" {}", element.value.to_sexpr()))
+                    .collect();
+                format!("(array{})", rendered)
+            }
+            Expression::Map(entries) => {
+                let rendered: String = entries
+                    .iter()
+                    .map(|(key, value)| format!(" ({:?} {})", key, value.value.to_sexpr()))
+                    .collect();
+                format!("(map{})", rendered)
+            }
+            Expression::Tuple(elements) => {
+                let rendered: String = elements
+                    .iter()
+                    .map(|element| format!(" {}", element.value.to_sexpr()))
+                    .collect();
+                format!("(tuple{})", rendered)
+            }
+            Expression::Index { target, index } => format!(
+                "(index {} {})",
+                target.value.to_sexpr(),
+                index.value.to_sexpr()
+            ),
+            Expression::Unary { operator, operand } => {
+                format!("({} {})", operator.symbol(), operand.value.to_sexpr())
+            }
+            Expression::Binary {
+                left,
+                operator,
+                right,
+            } => format!(
+                "({} {} {})",
+                operator.symbol(),
+                left.value.to_sexpr(),
+                right.value.to_sexpr()
+            ),
+            Expression::FunctionCall { name, arguments } => {
+                let rendered: String = arguments
+                    .iter()
+                    .map(|argument| format!(" {}", argument.value.to_sexpr()))
+                    .collect();
+                format!("(call {}{})", name, rendered)
+            }
+            Expression::MethodCall {
+                receiver,
+                name,
+                arguments,
+            } => {
+                let rendered: String = arguments
+                    .iter()
+                    .map(|argument| format!(" {}", argument.value.to_sexpr()))
+                    .collect();
+                format!(
+                    "(method {} {}{})",
+                    name,
+                    receiver.value.to_sexpr(),
+                    rendered
+                )
+            }
+            Expression::Lambda { parameters, body } => {
+                format!("(fn ({}){})", parameters.join(" "), sexpr_body(body))
+            }
+        }
+    }
+}
+
+/// Clone a statement list without recursing through expression nesting.
+///
+/// Statement nesting mirrors source block depth, which stays shallow, so the
+/// statement walk itself is recursive; every contained expression goes through
+/// [`deep_clone_expression`].
+pub fn deep_clone_statements(statements: &[Spanned<Statement>]) -> Vec<Spanned<Statement>> {
+    statements
+        .iter()
+        .map(|statement| Spanned::new(deep_clone_statement(&statement.value), statement.span))
+        .collect()
+}
+
+fn deep_clone_statement(statement: &Statement) -> Statement {
+    match statement {
+        Statement::Assignment { name, value } => Statement::Assignment {
+            name: name.clone(),
+            value: deep_clone_expression(value),
+        },
+        Statement::Desugared {
+            original,
+            statement,
+        } => Statement::Desugared {
+            original: original.clone(),
+            statement: Box::new(Spanned::new(
+                deep_clone_statement(&statement.value),
+                statement.span,
+            )),
+        },
+        Statement::Let {
+            name,
+            type_annotation,
+            value,
+        } => Statement::Let {
+            name: name.clone(),
+            type_annotation: *type_annotation,
+            value: deep_clone_expression(value),
+        },
+        Statement::IndexAssignment {
+            target,
+            index,
+            value,
+        } => Statement::IndexAssignment {
+            target: deep_clone_expression(target),
+            index: deep_clone_expression(index),
+            value: deep_clone_expression(value),
+        },
+        Statement::FunctionDefinition {
+            name,
+            parameters,
+            body,
+            doc,
+        } => Statement::FunctionDefinition {
+            name: name.clone(),
+            parameters: parameters.clone(),
+            body: deep_clone_statements(body),
+            doc: doc.clone(),
+        },
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => Statement::If {
+            condition: deep_clone_expression(condition),
+            then_branch: deep_clone_statements(then_branch),
+            else_branch: else_branch.as_deref().map(deep_clone_statements),
+        },
+        Statement::Match { scrutinee, arms } => Statement::Match {
+            scrutinee: deep_clone_expression(scrutinee),
+            arms: arms
+                .iter()
+                .map(|arm| MatchArm {
+                    pattern: arm.pattern.as_ref().map(deep_clone_expression),
+                    body: deep_clone_statements(&arm.body),
+                })
+                .collect(),
+        },
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+        } => Statement::While {
+            condition: deep_clone_expression(condition),
+            body: deep_clone_statements(body),
+            else_branch: else_branch.as_deref().map(deep_clone_statements),
+        },
+        Statement::ForIn {
+            variable,
+            iterable,
+            body,
+        } => Statement::ForIn {
+            variable: variable.clone(),
+            iterable: deep_clone_expression(iterable),
+            body: deep_clone_statements(body),
+        },
+        Statement::Return { value } => Statement::Return {
+            value: value.as_ref().map(deep_clone_expression),
+        },
+        Statement::Break => Statement::Break,
+        Statement::Continue => Statement::Continue,
+        Statement::Block(statements) => Statement::Block(deep_clone_statements(statements)),
+        Statement::Expression(expression) => {
+            Statement::Expression(deep_clone_expression(expression))
+        }
+    }
+}
+
+/// Clone an expression with an explicit work stack instead of recursion.
+///
+/// The derived `Clone` recurses once per nesting level, so a machine-generated
+/// expression tens of thousands of levels deep overflows the stack. This
+/// rebuilds the tree in post-order: children are cloned before their parent
+/// and popped off a result stack when the parent is assembled.
+pub fn deep_clone_expression(expression: &Spanned<Expression>) -> Spanned<Expression> {
+    enum Task<'a> {
+        Visit(&'a Spanned<Expression>),
+        Build(&'a Spanned<Expression>),
+    }
+
+    let mut tasks = vec![Task::Visit(expression)];
+    let mut results: Vec<Spanned<Expression>> = Vec::new();
+
+    while let Some(task) = tasks.pop() {
+        match task {
+            Task::Visit(node) => {
+                tasks.push(Task::Build(node));
+                // Children are pushed in source order; popping visits them in
+                // reverse, which leaves the first child on top of `results`
+                // when the parent is built.
+                match &node.value {
+                    Expression::Unary { operand, .. } => tasks.push(Task::Visit(operand)),
+                    Expression::Binary { left, right, .. } => {
+                        tasks.push(Task::Visit(left));
+                        tasks.push(Task::Visit(right));
+                    }
+                    Expression::Index { target, index } => {
+                        tasks.push(Task::Visit(target));
+                        tasks.push(Task::Visit(index));
+                    }
+                    Expression::FunctionCall { arguments, .. } => {
+                        for argument in arguments {
+                            tasks.push(Task::Visit(argument));
+                        }
+                    }
+                    Expression::MethodCall {
+                        receiver,
+                        arguments,
+                        ..
+                    } => {
+                        tasks.push(Task::Visit(receiver));
+                        for argument in arguments {
+                            tasks.push(Task::Visit(argument));
+                        }
+                    }
+                    Expression::Array(elements) | Expression::Tuple(elements) => {
+                        for element in elements {
+                            tasks.push(Task::Visit(element));
+                        }
+                    }
+                    Expression::Map(entries) => {
+                        for (_, value) in entries {
+                            tasks.push(Task::Visit(value));
+                        }
+                    }
+                    // A lambda's children are statements, cloned through
+                    // `deep_clone_statements` when the node is built.
+                    Expression::Null
+                    | Expression::Integer(_)
+                    | Expression::Float(_)
+                    | Expression::Boolean(_)
+                    | Expression::Char(_)
+                    | Expression::String(_)
+                    | Expression::Variable(_)
+                    | Expression::Lambda { .. } => {}
+                }
+            }
+            Task::Build(node) => {
+                let value = match &node.value {
+                    Expression::Null => Expression::Null,
+                    Expression::Integer(value) => Expression::Integer(*value),
+                    Expression::Float(value) => Expression::Float(*value),
+                    Expression::Boolean(value) => Expression::Boolean(*value),
+                    Expression::Char(value) => Expression::Char(*value),
+                    Expression::String(value) => Expression::String(value.clone()),
+                    Expression::Variable(name) => Expression::Variable(name.clone()),
+                    Expression::Unary { operator, .. } => Expression::Unary {
+                        operator: *operator,
+                        operand: Box::new(results.pop().expect("operand was cloned")),
+                    },
+                    Expression::Binary { operator, .. } => Expression::Binary {
+                        left: Box::new(results.pop().expect("left operand was cloned")),
+                        operator: *operator,
+                        right: Box::new(results.pop().expect("right operand was cloned")),
+                    },
+                    Expression::Index { .. } => Expression::Index {
+                        target: Box::new(results.pop().expect("index target was cloned")),
+                        index: Box::new(results.pop().expect("index was cloned")),
+                    },
+                    Expression::FunctionCall { name, arguments } => Expression::FunctionCall {
+                        name: name.clone(),
+                        arguments: (0..arguments.len())
+                            .map(|_| results.pop().expect("argument was cloned"))
+                            .collect(),
+                    },
+                    Expression::MethodCall {
+                        name, arguments, ..
+                    } => Expression::MethodCall {
+                        receiver: Box::new(results.pop().expect("receiver was cloned")),
+                        name: name.clone(),
+                        arguments: (0..arguments.len())
+                            .map(|_| results.pop().expect("argument was cloned"))
+                            .collect(),
+                    },
+                    Expression::Array(elements) => Expression::Array(
+                        (0..elements.len())
+                            .map(|_| results.pop().expect("element was cloned"))
+                            .collect(),
+                    ),
+                    Expression::Tuple(elements) => Expression::Tuple(
+                        (0..elements.len())
+                            .map(|_| results.pop().expect("element was cloned"))
+                            .collect(),
+                    ),
+                    Expression::Map(entries) => Expression::Map(
+                        entries
+                            .iter()
+                            .map(|(key, _)| {
+                                (key.clone(), results.pop().expect("entry value was cloned"))
+                            })
+                            .collect(),
+                    ),
+                    Expression::Lambda { parameters, body } => Expression::Lambda {
+                        parameters: parameters.clone(),
+                        body: deep_clone_statements(body),
+                    },
+                };
+                results.push(Spanned::new(value, node.span));
+            }
+        }
+    }
+
+    results.pop().expect("the root was cloned")
+}
+
+/// Renders a program as an indented tree, for debugging and tooling.
+pub struct AstDisplay<'a> {
+    program: &'a Program,
+}
+
+impl<'a> AstDisplay<'a> {
+    pub fn new(program: &'a Program) -> Self {
+        Self { program }
+    }
+}
+
+impl fmt::Display for AstDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Program")?;
+        for statement in &self.program.statements {
+            write_statement(f, &statement.value, 1)?;
+        }
+        Ok(())
+    }
+}
+
+fn indent(f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+    write!(f, "{}", "  ".repeat(depth))
+}
+
+fn write_statement(f: &mut fmt::Formatter<'_>, statement: &Statement, depth: usize) -> fmt::Result {
+    indent(f, depth)?;
+    match statement {
+        Statement::Assignment { name, value } => {
+            writeln!(f, "Assignment {}", name)?;
+            write_expression(f, &value.value, depth + 1)
+        }
+        Statement::Desugared {
+            original,
+            statement,
+        } => {
+            writeln!(f, "Desugared `{}`", original)?;
+            write_statement(f, &statement.value, depth + 1)
+        }
+        Statement::Let {
+            name,
+            type_annotation,
+            value,
+        } => {
+            match type_annotation {
+                Some(annotation) => writeln!(f, "Let {}: {}", name, annotation.name())?,
+                None => writeln!(f, "Let {}", name)?,
+            }
+            write_expression(f, &value.value, depth + 1)
+        }
+        Statement::IndexAssignment {
+            target,
+            index,
+            value,
+        } => {
+            writeln!(f, "IndexAssignment")?;
+            write_expression(f, &target.value, depth + 1)?;
+            write_expression(f, &index.value, depth + 1)?;
+            write_expression(f, &value.value, depth + 1)
+        }
+        Statement::FunctionDefinition {
+            name,
+            parameters,
+            body,
+            ..
+        } => {
+            writeln!(f, "FunctionDefinition {}({})", name, parameters.join(", "))?;
+            for statement in body {
+                write_statement(f, &statement.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Statement::If {
+            condition,
+            then_branch,
+            else_branch,
+        } => {
+            writeln!(f, "If")?;
+            write_expression(f, &condition.value, depth + 1)?;
+            indent(f, depth + 1)?;
+            writeln!(f, "Then")?;
+            for statement in then_branch {
+                write_statement(f, &statement.value, depth + 2)?;
+            }
+            if let Some(else_branch) = else_branch {
+                indent(f, depth + 1)?;
+                writeln!(f, "Else")?;
+                for statement in else_branch {
+                    write_statement(f, &statement.value, depth + 2)?;
+                }
+            }
+            Ok(())
+        }
+        Statement::Match { scrutinee, arms } => {
+            writeln!(f, "Match")?;
+            write_expression(f, &scrutinee.value, depth + 1)?;
+            for arm in arms {
+                indent(f, depth + 1)?;
+                match &arm.pattern {
+                    Some(pattern) => {
+                        writeln!(f, "Arm")?;
+                        write_expression(f, &pattern.value, depth + 2)?;
+                    }
+                    None => writeln!(f, "Arm _")?,
+                }
+                for statement in &arm.body {
+                    write_statement(f, &statement.value, depth + 2)?;
+                }
+            }
+            Ok(())
+        }
+        Statement::While {
+            condition,
+            body,
+            else_branch,
+        } => {
+            writeln!(f, "While")?;
+            write_expression(f, &condition.value, depth + 1)?;
+            for statement in body {
+                write_statement(f, &statement.value, depth + 1)?;
+            }
+            if let Some(else_branch) = else_branch {
+                indent(f, depth + 1)?;
+                writeln!(f, "Else")?;
+                for statement in else_branch {
+                    write_statement(f, &statement.value, depth + 2)?;
+                }
+            }
+            Ok(())
+        }
+        Statement::ForIn {
+            variable,
+            iterable,
+            body,
+        } => {
+            writeln!(f, "ForIn {}", variable)?;
+            write_expression(f, &iterable.value, depth + 1)?;
+            for statement in body {
+                write_statement(f, &statement.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Statement::Return { value } => {
+            writeln!(f, "Return")?;
+            if let Some(value) = value {
+                write_expression(f, &value.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Statement::Break => writeln!(f, "Break"),
+        Statement::Continue => writeln!(f, "Continue"),
+        Statement::Block(statements) => {
+            writeln!(f, "Block")?;
+            for statement in statements {
+                write_statement(f, &statement.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Statement::Expression(expression) => {
+            writeln!(f, "ExpressionStatement")?;
+            write_expression(f, &expression.value, depth + 1)
+        }
+    }
+}
+
+fn write_expression(
+    f: &mut fmt::Formatter<'_>,
+    expression: &Expression,
+    depth: usize,
+) -> fmt::Result {
+    indent(f, depth)?;
+    match expression {
+        Expression::Null => writeln!(f, "Null"),
+        Expression::Integer(value) => writeln!(f, "Integer {}", value),
+        Expression::Float(value) => writeln!(f, "Float {:?}", value),
+        Expression::Boolean(value) => writeln!(f, "Boolean {}", value),
+        Expression::Char(value) => writeln!(f, "Char {:?}", value),
+        Expression::String(value) => writeln!(f, "String {:?}", value),
+        Expression::Variable(name) => writeln!(f, "Variable {}", name),
+        Expression::Array(elements) => {
+            writeln!(f, "Array")?;
+            for element in elements {
+                write_expression(f, &element.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Expression::Tuple(elements) => {
+            writeln!(f, "Tuple")?;
+            for element in elements {
+                write_expression(f, &element.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Expression::Map(entries) => {
+            writeln!(f, "Map")?;
+            for (key, value) in entries {
+                indent(f, depth + 1)?;
+                writeln!(f, "Entry {:?}", key)?;
+                write_expression(f, &value.value, depth + 2)?;
+            }
+            Ok(())
+        }
+        Expression::Index { target, index } => {
+            writeln!(f, "Index")?;
+            write_expression(f, &target.value, depth + 1)?;
+            write_expression(f, &index.value, depth + 1)
+        }
+        Expression::Unary { operator, operand } => {
+            writeln!(f, "Unary {}", operator.symbol())?;
+            write_expression(f, &operand.value, depth + 1)
+        }
+        Expression::Binary {
+            left,
+            operator,
+            right,
+        } => {
+            writeln!(f, "Binary {}", operator.symbol())?;
+            write_expression(f, &left.value, depth + 1)?;
+            write_expression(f, &right.value, depth + 1)
+        }
+        Expression::FunctionCall { name, arguments } => {
+            writeln!(f, "FunctionCall {}", name)?;
+            for argument in arguments {
+                write_expression(f, &argument.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Expression::MethodCall {
+            receiver,
+            name,
+            arguments,
+        } => {
+            writeln!(f, "MethodCall {}", name)?;
+            write_expression(f, &receiver.value, depth + 1)?;
+            for argument in arguments {
+                write_expression(f, &argument.value, depth + 1)?;
+            }
+            Ok(())
+        }
+        Expression::Lambda { parameters, body } => {
+            writeln!(f, "Lambda ({})", parameters.join(", "))?;
+            for statement in body {
+                write_statement(f, &statement.value, depth + 1)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Span;
+
+    #[test]
+    fn deep_clone_matches_derived_clone_on_a_small_tree() {
+        let expression = Spanned::new(
+            Expression::Binary {
+                left: Box::new(Spanned::new(Expression::Integer(1), Span::new(0, 1))),
+                operator: BinaryOperator::Add,
+                right: Box::new(Spanned::new(
+                    Expression::FunctionCall {
+                        name: "f".to_string(),
+                        arguments: vec![
+                            Spanned::new(Expression::Variable("x".to_string()), Span::new(6, 7)),
+                            Spanned::new(Expression::Integer(2), Span::new(9, 10)),
+                        ],
+                    },
+                    Span::new(4, 11),
+                )),
+            },
+            Span::new(0, 11),
+        );
+        assert_eq!(deep_clone_expression(&expression), expression.clone());
+    }
+
+    #[test]
+    fn statement_to_sexpr() {
+        // `x = 1 + 2;`
+        let statement = Statement::Assignment {
+            name: "x".to_string(),
+            value: Spanned::new(
+                Expression::Binary {
+                    left: Box::new(Spanned::new(Expression::Integer(1), Span::new(4, 5))),
+                    operator: BinaryOperator::Add,
+                    right: Box::new(Spanned::new(Expression::Integer(2), Span::new(8, 9))),
+                },
+                Span::new(4, 9),
+            ),
+        };
+        assert_eq!(statement.to_sexpr(), "(assign x (+ 1 2))");
+    }
+
+    #[test]
+    fn call_and_string_to_sexpr() {
+        let expression = Expression::FunctionCall {
+            name: "print".to_string(),
+            arguments: vec![Spanned::new(
+                Expression::String("hi".to_string()),
+                Span::new(6, 10),
+            )],
+        };
+        assert_eq!(expression.to_sexpr(), "(call print \"hi\")");
+    }
+
+    #[test]
+    fn deep_clone_survives_a_very_deep_expression() {
+        let depth = 200_000;
+        let mut expression = Spanned::new(Expression::Integer(1), Span::new(0, 1));
+        for _ in 0..depth {
+            expression = Spanned::new(
+                Expression::Unary {
+                    operator: UnaryOperator::Negate,
+                    operand: Box::new(expression),
+                },
+                Span::new(0, 1),
+            );
+        }
+
+        let clone = deep_clone_expression(&expression);
+
+        // Walk the clone iteratively; the derived `PartialEq` would recurse.
+        let mut levels = 0;
+        let mut cursor = &clone;
+        while let Expression::Unary { operand, .. } = &cursor.value {
+            levels += 1;
+            cursor = operand;
+        }
+        assert_eq!(levels, depth);
+        assert_eq!(cursor.value, Expression::Integer(1));
+
+        // The derived `Drop` also recurses once per level, so leak both trees
+        // rather than overflow the test thread's stack tearing them down.
+        std::mem::forget(expression);
+        std::mem::forget(clone);
+    }
+}
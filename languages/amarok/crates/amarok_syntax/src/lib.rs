@@ -0,0 +1,9 @@
+//! Shared syntax definitions for the Amarok language: source spans and the
+//! abstract syntax tree produced by the parser.
+
+pub mod ast;
+mod line_index;
+mod span;
+
+pub use line_index::LineIndex;
+pub use span::{Span, Spanned};
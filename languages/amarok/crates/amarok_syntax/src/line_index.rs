@@ -0,0 +1,100 @@
+//! Mapping byte offsets into human line/column coordinates.
+
+/// Precomputed line starts for one source text, so repeated span-to-position
+/// conversions are a binary search instead of a scan from the top.
+///
+/// Build it once per source; every diagnostic renderer and future tool that
+/// reports positions shares the same 1-based convention through it.
+pub struct LineIndex<'a> {
+    source: &'a str,
+    /// Byte offset of the first character of each line.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            source
+                .char_indices()
+                .filter(|(_, character)| *character == '\n')
+                .map(|(offset, _)| offset + 1),
+        );
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    /// The 1-based line and column of a byte offset. Columns count
+    /// characters, not bytes, and a newline belongs to the line it ends.
+    /// Offsets past the end clamp to the final position.
+    pub fn line_col(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|&start| start <= offset);
+        let line_start = self.line_starts[line - 1];
+        let column = self.source[line_start..offset].chars().count() + 1;
+        (line, column)
+    }
+
+    /// The text of a 1-based line, without its trailing newline.
+    pub fn line_text(&self, line: usize) -> Option<&'a str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map(|next_start| next_start - 1)
+            .unwrap_or(self.source.len());
+        Some(&self.source[start..end])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_col_is_one_based_on_the_first_line() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.line_col(0), (1, 1));
+        assert_eq!(index.line_col(1), (1, 2));
+    }
+
+    #[test]
+    fn line_col_on_the_last_line() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.line_col(3), (2, 1));
+        assert_eq!(index.line_col(4), (2, 2));
+        assert_eq!(index.line_col(5), (2, 3));
+    }
+
+    #[test]
+    fn a_newline_offset_belongs_to_the_line_it_ends() {
+        let index = LineIndex::new("ab\ncd");
+        assert_eq!(index.line_col(2), (1, 3));
+    }
+
+    #[test]
+    fn offsets_past_the_end_clamp() {
+        let index = LineIndex::new("ab");
+        assert_eq!(index.line_col(99), (1, 3));
+    }
+
+    #[test]
+    fn columns_count_characters_not_bytes() {
+        let index = LineIndex::new("héllo");
+        // `é` is two bytes; the offset after it is still column 3.
+        assert_eq!(index.line_col(3), (1, 3));
+    }
+
+    #[test]
+    fn line_text_excludes_the_newline() {
+        let index = LineIndex::new("ab\ncd\n");
+        assert_eq!(index.line_text(1), Some("ab"));
+        assert_eq!(index.line_text(2), Some("cd"));
+        // The trailing newline opens an empty final line.
+        assert_eq!(index.line_text(3), Some(""));
+        assert_eq!(index.line_text(0), None);
+        assert_eq!(index.line_text(4), None);
+    }
+}
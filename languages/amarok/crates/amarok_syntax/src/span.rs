@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// A half-open byte range into the original source text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`.
+    pub fn merge(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+/// A value paired with the span it was parsed from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spanned<T> {
+    pub value: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(value: T, span: Span) -> Self {
+        Self { value, span }
+    }
+
+    /// Transform the wrapped value while keeping the span.
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned::new(f(self.value), self.span)
+    }
+
+    /// Pair two spanned values under one span covering both inputs.
+    pub fn zip<U>(self, other: Spanned<U>) -> Spanned<(T, U)> {
+        Spanned::new((self.value, other.value), self.span.merge(other.span))
+    }
+
+    /// Take the value and span apart.
+    pub fn split(self) -> (Span, T) {
+        (self.span, self.value)
+    }
+
+    /// Swap in a new value, returning the old one; the span stays put.
+    /// Transformation passes rewrite a node in place with this instead of
+    /// reconstructing the wrapper.
+    pub fn replace(&mut self, value: T) -> T {
+        std::mem::replace(&mut self.value, value)
+    }
+
+    /// A mutable borrow of the wrapped value, for in-place edits.
+    pub fn value_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_spans() {
+        assert_eq!(Span::new(1, 4).merge(Span::new(2, 9)), Span::new(1, 9));
+    }
+
+    #[test]
+    fn map_preserves_span() {
+        let spanned = Spanned::new(5, Span::new(3, 4)).map(|n| n * 2);
+        assert_eq!(spanned.value, 10);
+        assert_eq!(spanned.span, Span::new(3, 4));
+    }
+
+    #[test]
+    fn zip_merges_both_spans() {
+        let left = Spanned::new("a", Span::new(0, 1));
+        let right = Spanned::new(7, Span::new(4, 5));
+        let zipped = left.zip(right);
+        assert_eq!(zipped.value, ("a", 7));
+        assert_eq!(zipped.span, Span::new(0, 5));
+    }
+
+    #[test]
+    fn replace_returns_the_old_value_and_keeps_the_span() {
+        let mut spanned = Spanned::new(5, Span::new(3, 4));
+        let old = spanned.replace(8);
+        assert_eq!(old, 5);
+        assert_eq!(spanned.value, 8);
+        assert_eq!(spanned.span, Span::new(3, 4));
+    }
+
+    #[test]
+    fn value_mut_edits_in_place() {
+        let mut spanned = Spanned::new(String::from("ab"), Span::new(0, 2));
+        spanned.value_mut().push('c');
+        assert_eq!(spanned.value, "abc");
+        assert_eq!(spanned.span, Span::new(0, 2));
+    }
+
+    #[test]
+    fn split_returns_span_and_value() {
+        let (span, value) = Spanned::new(true, Span::new(2, 6)).split();
+        assert_eq!(span, Span::new(2, 6));
+        assert!(value);
+    }
+}
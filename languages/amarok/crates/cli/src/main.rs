@@ -0,0 +1,177 @@
+//! The `amarokc` command: the native Amarok compiler.
+
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+use driver::{bundled_runtime_interface, compile_file, CompilationOptions};
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Compile {
+        input: PathBuf,
+        output: Option<PathBuf>,
+    },
+    Version,
+    Help,
+}
+
+impl Command {
+    fn parse(arguments: &[String]) -> Result<Command, String> {
+        match arguments.first().map(String::as_str) {
+            Some("compile") => {
+                let mut input = None;
+                let mut output = None;
+                let mut rest = arguments[1..].iter();
+                while let Some(argument) = rest.next() {
+                    match argument.as_str() {
+                        "-o" | "--output" => {
+                            let path = rest
+                                .next()
+                                .ok_or_else(|| format!("{} requires a path", argument))?;
+                            output = Some(PathBuf::from(path));
+                        }
+                        _ => input = Some(PathBuf::from(argument)),
+                    }
+                }
+                let input = input.ok_or_else(|| "compile requires an input file".to_string())?;
+                Ok(Command::Compile { input, output })
+            }
+            Some("version") => Ok(Command::Version),
+            Some("help") | None => Ok(Command::Help),
+            Some(other) => Err(format!("unknown command: {}", other)),
+        }
+    }
+}
+
+/// The options `amarokc compile` hands to the driver. The bundled runtime's
+/// functions are declared callable: the driver links that runtime into every
+/// executable anyway, so without the declarations a program that prints
+/// could never compile here.
+fn compilation_options(output: Option<PathBuf>) -> CompilationOptions {
+    CompilationOptions {
+        output_path: output,
+        runtime: bundled_runtime_interface(),
+        ..CompilationOptions::simple()
+    }
+}
+
+/// The product version first (scripts key off that line), then the codegen
+/// backend and host target so bug reports carry the details that matter.
+fn version_output() -> String {
+    format!(
+        "amarokc {}\n{}",
+        env!("CARGO_PKG_VERSION"),
+        codegen::backend_description()
+    )
+}
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    let command = match Command::parse(&arguments) {
+        Ok(command) => command,
+        Err(message) => {
+            eprintln!("error: {}", message);
+            process::exit(2);
+        }
+    };
+
+    match command {
+        Command::Compile { input, output } => {
+            let options = compilation_options(output);
+            match compile_file(&input, &options) {
+                Ok(output) => println!("wrote {}", output.display()),
+                Err(error) => {
+                    // Re-read the source for the caret line; if that fails
+                    // the rendering degrades to the bare message.
+                    let source = std::fs::read_to_string(&input).unwrap_or_default();
+                    let file = input.display().to_string();
+                    eprint!("{}", driver::render_error(&file, &source, &error));
+                    process::exit(1);
+                }
+            }
+        }
+        Command::Version => println!("{}", version_output()),
+        Command::Help => {
+            println!("usage: amarokc <compile [-o FILE] FILE | version | help>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parse_compile_command() {
+        assert_eq!(
+            Command::parse(&args(&["compile", "in.amarok"])).unwrap(),
+            Command::Compile {
+                input: PathBuf::from("in.amarok"),
+                output: None,
+            }
+        );
+    }
+
+    #[test]
+    fn parse_compile_with_output_flag() {
+        for flag in ["-o", "--output"] {
+            assert_eq!(
+                Command::parse(&args(&["compile", "in.amarok", flag, "custom"])).unwrap(),
+                Command::Compile {
+                    input: PathBuf::from("in.amarok"),
+                    output: Some(PathBuf::from("custom")),
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn compile_without_input_is_an_error() {
+        assert!(Command::parse(&args(&["compile"])).is_err());
+    }
+
+    #[test]
+    fn output_flag_without_a_path_is_an_error() {
+        assert!(Command::parse(&args(&["compile", "in.amarok", "-o"])).is_err());
+    }
+
+    #[test]
+    fn compile_with_output_flag_writes_the_named_file() {
+        let directory = std::env::temp_dir().join(format!("amarok-cli-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let source = directory.join("custom_source.amk");
+        std::fs::write(&source, "1 + 2;").unwrap();
+        let custom = directory.join("custom");
+
+        let parsed = Command::parse(&args(&[
+            "compile",
+            source.to_str().unwrap(),
+            "-o",
+            custom.to_str().unwrap(),
+        ]))
+        .unwrap();
+        let Command::Compile { input, output } = parsed else {
+            panic!("expected a compile command");
+        };
+        compile_file(&input, &compilation_options(output)).unwrap();
+        assert!(custom.exists());
+    }
+
+    #[test]
+    fn parse_version_command() {
+        assert_eq!(Command::parse(&args(&["version"])).unwrap(), Command::Version);
+    }
+
+    #[test]
+    fn version_reports_the_backend_and_target() {
+        let output = version_output();
+        assert!(output.starts_with(&format!("amarokc {}\n", env!("CARGO_PKG_VERSION"))));
+        assert!(output.contains("backend: cranelift "));
+        assert!(output.lines().any(|line| line.starts_with("target: ")));
+    }
+}
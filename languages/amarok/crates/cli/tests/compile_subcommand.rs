@@ -0,0 +1,35 @@
+//! End-to-end checks of `amarokc compile` against real script files.
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn write_script(name: &str, contents: &str) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("amarokc-compile-{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let path = directory.join(name);
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn a_compiled_program_prints_through_the_bundled_runtime() {
+    let script = write_script("prints.amk", "amarok_print_int(42);");
+    let executable = script.with_file_name("prints");
+    let output = Command::new(env!("CARGO_BIN_EXE_amarokc"))
+        .arg("compile")
+        .arg(&script)
+        .arg("-o")
+        .arg(&executable)
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "stderr was: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = Command::new(&executable).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+    assert_eq!(output.status.code(), Some(0));
+}
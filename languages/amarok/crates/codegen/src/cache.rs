@@ -0,0 +1,134 @@
+//! An opt-in cache over [`compile_program_to_object`], for build tools that
+//! invoke codegen repeatedly on mostly unchanged programs.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use syntax::ast::ProgramNode;
+
+use crate::lower::compile_program_to_object;
+use crate::runtime::RuntimeInterface;
+use crate::CodegenError;
+
+/// A caller-owned, in-memory cache of emitted object bytes, keyed on a
+/// structural hash of the program together with the module name, runtime
+/// interface, and entry name — everything that feeds the emitted object.
+///
+/// The cache is purely additive: entries are never evicted, so it should
+/// live for one build session, not a long-running daemon.
+#[derive(Debug, Default)]
+pub struct CompileCache {
+    objects: HashMap<u64, Vec<u8>>,
+    hits: usize,
+}
+
+impl CompileCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile `program` as [`compile_program_to_object`] would, returning
+    /// the previously emitted bytes when an identical compilation already
+    /// went through this cache. Errors are not cached: a failing program is
+    /// re-lowered each call, so it keeps producing its diagnostic.
+    pub fn compile(
+        &mut self,
+        program: &ProgramNode,
+        module_name: &str,
+        runtime: &RuntimeInterface,
+        entry_name: &str,
+    ) -> Result<Vec<u8>, CodegenError> {
+        let key = cache_key(program, module_name, runtime, entry_name);
+        if let Some(object_bytes) = self.objects.get(&key) {
+            self.hits += 1;
+            return Ok(object_bytes.clone());
+        }
+        let object_bytes = compile_program_to_object(program, module_name, runtime, entry_name)?;
+        self.objects.insert(key, object_bytes.clone());
+        Ok(object_bytes)
+    }
+
+    /// How many calls returned cached bytes instead of re-lowering.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+}
+
+fn cache_key(
+    program: &ProgramNode,
+    module_name: &str,
+    runtime: &RuntimeInterface,
+    entry_name: &str,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    program.hash(&mut hasher);
+    module_name.hash(&mut hasher);
+    runtime.hash(&mut hasher);
+    entry_name.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(source: &str) -> ProgramNode {
+        let tokens = syntax::lexer::lex(source).expect("test sources should lex");
+        syntax::parser::parse(&tokens).expect("test sources should parse")
+    }
+
+    #[test]
+    fn an_identical_program_is_served_from_the_cache() {
+        let mut cache = CompileCache::new();
+        let runtime = RuntimeInterface::empty();
+        let first = cache
+            .compile(&parse("let x = 2; x * 21;"), "test", &runtime, "main")
+            .unwrap();
+        let second = cache
+            .compile(&parse("let x = 2; x * 21;"), "test", &runtime, "main")
+            .unwrap();
+        assert_eq!(first, second);
+        // The second call was a hit, so lowering ran exactly once.
+        assert_eq!(cache.hits(), 1);
+    }
+
+    #[test]
+    fn a_different_program_misses() {
+        let mut cache = CompileCache::new();
+        let runtime = RuntimeInterface::empty();
+        cache
+            .compile(&parse("1 + 2;"), "test", &runtime, "main")
+            .unwrap();
+        cache
+            .compile(&parse("1 + 3;"), "test", &runtime, "main")
+            .unwrap();
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn the_entry_name_is_part_of_the_key() {
+        let mut cache = CompileCache::new();
+        let runtime = RuntimeInterface::empty();
+        cache
+            .compile(&parse("1 + 2;"), "test", &runtime, "main")
+            .unwrap();
+        cache
+            .compile(&parse("1 + 2;"), "test", &runtime, "amarok_entry")
+            .unwrap();
+        assert_eq!(cache.hits(), 0);
+    }
+
+    #[test]
+    fn errors_are_not_cached() {
+        let mut cache = CompileCache::new();
+        let runtime = RuntimeInterface::empty();
+        for _ in 0..2 {
+            let error = cache
+                .compile(&parse("missing;"), "test", &runtime, "main")
+                .unwrap_err();
+            assert_eq!(error.message, "Undefined variable: missing");
+        }
+        assert_eq!(cache.hits(), 0);
+    }
+}
@@ -0,0 +1,55 @@
+//! Native code generation for Amarok via Cranelift.
+
+pub mod cache;
+pub mod lower;
+pub mod runtime;
+
+use std::fmt;
+
+pub use cache::CompileCache;
+pub use lower::{compile_program_to_ir, compile_program_to_object};
+pub use runtime::{RuntimeFunction, RuntimeInterface, RuntimeValueType};
+
+/// The backend's version and the host target it generates code for, one
+/// `key: value` per line — appended to `amarokc version` so bug reports say
+/// exactly what did the compiling.
+pub fn backend_description() -> String {
+    let target = cranelift_native::builder()
+        .map(|builder| builder.triple().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("backend: cranelift {}\ntarget: {}", cranelift::VERSION, target)
+}
+
+/// A failure while lowering a program to native code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodegenError {
+    pub message: String,
+    /// Where in the source the error arose. Errors raised for a specific AST
+    /// node carry its position; backend setup failures have none.
+    pub position: Option<syntax::ast::Position>,
+}
+
+impl CodegenError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            position: None,
+        }
+    }
+
+    /// An error pointing at a specific source position.
+    pub fn at(message: impl Into<String>, position: syntax::ast::Position) -> Self {
+        Self {
+            message: message.into(),
+            position: Some(position),
+        }
+    }
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for CodegenError {}
@@ -0,0 +1,613 @@
+//! Lowering from the `syntax` AST to Cranelift IR.
+//!
+//! A program becomes a single exported `main` function returning `i64`: the
+//! value of the last expression statement, or `0` if there is none.
+
+use std::collections::HashMap;
+
+use cranelift::codegen::ir::FuncRef;
+use cranelift::prelude::*;
+use cranelift_module::{default_libcall_names, Linkage, Module};
+use cranelift_object::{ObjectBuilder, ObjectModule};
+use syntax::ast::{BinaryOperator, ExpressionNode, Position, ProgramNode, StatementNode};
+
+use crate::runtime::{RuntimeInterface, RuntimeValueType};
+use crate::CodegenError;
+
+fn runtime_type(ty: RuntimeValueType) -> Type {
+    match ty {
+        RuntimeValueType::Int => types::I64,
+        RuntimeValueType::Float => types::F64,
+    }
+}
+
+/// A lowered value together with its Cranelift type — `I64` for integers,
+/// `F64` for floats.
+#[derive(Debug, Clone, Copy)]
+struct TypedValue {
+    value: Value,
+    ty: Type,
+}
+
+/// An imported runtime function, ready to call from the function being built.
+struct RuntimeCallee {
+    func_ref: FuncRef,
+    parameters: Vec<Type>,
+    returns: Option<Type>,
+}
+
+/// The blocks `break` and `continue` branch to for one enclosing loop:
+/// `continue` re-enters the header (where the condition re-evaluates),
+/// `break` jumps to the exit.
+struct LoopBlocks {
+    header: Block,
+    exit: Block,
+}
+
+/// Per-function lowering state: the mapping from Amarok variables to
+/// Cranelift variables and their types, plus the callable runtime imports.
+pub struct CodegenContext {
+    variables: HashMap<String, (Variable, Type)>,
+    runtime_functions: HashMap<String, RuntimeCallee>,
+    next_variable_index: usize,
+    /// Enclosing loops, innermost last; `break`/`continue` target the top.
+    loop_blocks: Vec<LoopBlocks>,
+}
+
+impl CodegenContext {
+    fn new() -> Self {
+        Self {
+            variables: HashMap::new(),
+            runtime_functions: HashMap::new(),
+            next_variable_index: 0,
+            loop_blocks: Vec::new(),
+        }
+    }
+
+    fn declare_variable(&mut self, builder: &mut FunctionBuilder, name: &str, ty: Type) -> Variable {
+        if let Some((variable, existing_ty)) = self.variables.get(name) {
+            if *existing_ty == ty {
+                return *variable;
+            }
+            // A `let` rebinding the name at a different type gets a fresh
+            // Cranelift variable; the old one simply goes unused.
+        }
+        let variable = Variable::new(self.next_variable_index);
+        self.next_variable_index += 1;
+        builder.declare_var(variable, ty);
+        self.variables.insert(name.to_string(), (variable, ty));
+        variable
+    }
+}
+
+/// Compile `program` into a relocatable object file, returned as raw bytes.
+///
+/// The entry function is exported under `entry_name` — usually `main`, but
+/// embedders linking the object into a larger program pick something like
+/// `amarok_entry` and call it from their own `main`.
+///
+/// Every function in `runtime` is declared as an import; the resulting object
+/// links only if something else defines those symbols (see
+/// [`crate::runtime`] for the ABI).
+pub fn compile_program_to_object(
+    program: &ProgramNode,
+    module_name: &str,
+    runtime: &RuntimeInterface,
+    entry_name: &str,
+) -> Result<Vec<u8>, CodegenError> {
+    compile(program, module_name, runtime, entry_name).map(|(object_bytes, _)| object_bytes)
+}
+
+/// The entry function's Cranelift IR in textual form, for inspection and
+/// tests asserting on the emitted instructions; the object is discarded.
+pub fn compile_program_to_ir(
+    program: &ProgramNode,
+    module_name: &str,
+    runtime: &RuntimeInterface,
+    entry_name: &str,
+) -> Result<String, CodegenError> {
+    compile(program, module_name, runtime, entry_name).map(|(_, ir)| ir)
+}
+
+fn compile(
+    program: &ProgramNode,
+    module_name: &str,
+    runtime: &RuntimeInterface,
+    entry_name: &str,
+) -> Result<(Vec<u8>, String), CodegenError> {
+    let mut flag_builder = settings::builder();
+    flag_builder
+        .set("is_pic", "true")
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+    let isa_builder = cranelift_native::builder().map_err(CodegenError::new)?;
+    let isa = isa_builder
+        .finish(settings::Flags::new(flag_builder))
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+
+    let object_builder = ObjectBuilder::new(isa, module_name, default_libcall_names())
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+    let mut module = ObjectModule::new(object_builder);
+
+    let mut imported_functions = Vec::new();
+    for function in &runtime.functions {
+        let mut signature = module.make_signature();
+        for parameter in &function.parameters {
+            signature.params.push(AbiParam::new(runtime_type(*parameter)));
+        }
+        if let Some(returns) = function.returns {
+            signature.returns.push(AbiParam::new(runtime_type(returns)));
+        }
+        let function_id = module
+            .declare_function(&function.name, Linkage::Import, &signature)
+            .map_err(|error| CodegenError::new(error.to_string()))?;
+        imported_functions.push((function, function_id));
+    }
+
+    let mut context = module.make_context();
+    context
+        .func
+        .signature
+        .returns
+        .push(AbiParam::new(types::I64));
+
+    let mut builder_context = FunctionBuilderContext::new();
+    let mut builder = FunctionBuilder::new(&mut context.func, &mut builder_context);
+    let entry_block = builder.create_block();
+    builder.append_block_params_for_function_params(entry_block);
+    builder.switch_to_block(entry_block);
+    builder.seal_block(entry_block);
+
+    let mut codegen_context = CodegenContext::new();
+    for (function, function_id) in imported_functions {
+        let func_ref = module.declare_func_in_func(function_id, builder.func);
+        codegen_context.runtime_functions.insert(
+            function.name.clone(),
+            RuntimeCallee {
+                func_ref,
+                parameters: function.parameters.iter().map(|ty| runtime_type(*ty)).collect(),
+                returns: function.returns.map(runtime_type),
+            },
+        );
+    }
+    let mut last_value = None;
+    for statement in &program.statements {
+        last_value = compile_statement(&mut builder, &mut codegen_context, statement)?;
+    }
+    let return_value = match last_value {
+        // `main` returns `i64`; a float result is truncated toward zero.
+        Some(TypedValue { value, ty }) if ty == types::F64 => {
+            builder.ins().fcvt_to_sint(types::I64, value)
+        }
+        Some(TypedValue { value, .. }) => value,
+        None => builder.ins().iconst(types::I64, 0),
+    };
+    builder.ins().return_(&[return_value]);
+    builder.finalize();
+    let ir = context.func.display().to_string();
+
+    let function_id = module
+        .declare_function(entry_name, Linkage::Export, &context.func.signature)
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+    module
+        .define_function(function_id, &mut context)
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+    module.clear_context(&mut context);
+
+    let object_bytes = module
+        .finish()
+        .emit()
+        .map_err(|error| CodegenError::new(error.to_string()))?;
+    Ok((object_bytes, ir))
+}
+
+fn compile_statement(
+    builder: &mut FunctionBuilder,
+    context: &mut CodegenContext,
+    statement: &StatementNode,
+) -> Result<Option<TypedValue>, CodegenError> {
+    match statement {
+        StatementNode::Let { name, value, .. } => {
+            let value = compile_expression(builder, context, value)?;
+            let variable = context.declare_variable(builder, name, value.ty);
+            builder.def_var(variable, value.value);
+            Ok(None)
+        }
+        StatementNode::Expression { expression, .. } => {
+            Ok(Some(compile_expression(builder, context, expression)?))
+        }
+        StatementNode::While { condition, body, .. } => {
+            let header = builder.create_block();
+            let body_block = builder.create_block();
+            let exit = builder.create_block();
+
+            builder.ins().jump(header, &[]);
+            builder.switch_to_block(header);
+            let condition = compile_condition(builder, context, condition)?;
+            builder.ins().brif(condition, body_block, &[], exit, &[]);
+
+            // The body's only predecessor is the header's branch; `break`
+            // and `continue` jump out of it, never into it.
+            builder.switch_to_block(body_block);
+            builder.seal_block(body_block);
+            context.loop_blocks.push(LoopBlocks { header, exit });
+            let mut body_result = Ok(());
+            for statement in body {
+                if let Err(error) = compile_statement(builder, context, statement) {
+                    body_result = Err(error);
+                    break;
+                }
+            }
+            context.loop_blocks.pop();
+            body_result?;
+            builder.ins().jump(header, &[]);
+            // All back edges (the body's fall-through and any `continue`)
+            // are in place, so the header's predecessors are complete.
+            builder.seal_block(header);
+
+            builder.switch_to_block(exit);
+            builder.seal_block(exit);
+            Ok(None)
+        }
+        StatementNode::Break { position } => {
+            let Some(loop_blocks) = context.loop_blocks.last() else {
+                return Err(CodegenError::at("'break' outside of a loop", *position));
+            };
+            builder.ins().jump(loop_blocks.exit, &[]);
+            start_unreachable_block(builder);
+            Ok(None)
+        }
+        StatementNode::Continue { position } => {
+            let Some(loop_blocks) = context.loop_blocks.last() else {
+                return Err(CodegenError::at("'continue' outside of a loop", *position));
+            };
+            builder.ins().jump(loop_blocks.header, &[]);
+            start_unreachable_block(builder);
+            Ok(None)
+        }
+    }
+}
+
+/// Lower a condition to a value `brif` can branch on. A comparison in
+/// condition position branches on the comparison flag itself — the peephole
+/// skips materializing a 0/1 integer only to re-compare it against zero.
+/// Any other condition must be an integer, and non-zero keeps looping.
+fn compile_condition(
+    builder: &mut FunctionBuilder,
+    context: &mut CodegenContext,
+    condition: &ExpressionNode,
+) -> Result<Value, CodegenError> {
+    if let ExpressionNode::Binary {
+        left,
+        operator: operator @ (BinaryOperator::Less | BinaryOperator::Greater),
+        right,
+        ..
+    } = condition
+    {
+        let left = compile_expression(builder, context, left)?;
+        let right = compile_expression(builder, context, right)?;
+        return Ok(comparison_flag(builder, *operator, left, right));
+    }
+    let position = condition.position();
+    let condition = compile_expression(builder, context, condition)?;
+    if condition.ty != types::I64 {
+        return Err(CodegenError::at(
+            "a while condition must be an integer; non-zero keeps looping",
+            position,
+        ));
+    }
+    Ok(condition.value)
+}
+
+/// Lower `left operator right` to a single comparison instruction and
+/// return the narrow flag value. Mixed int/float operands promote the
+/// integer side, as in arithmetic.
+fn comparison_flag(
+    builder: &mut FunctionBuilder,
+    operator: BinaryOperator,
+    left: TypedValue,
+    right: TypedValue,
+) -> Value {
+    if left.ty == types::F64 || right.ty == types::F64 {
+        let left = promote_to_f64(builder, left);
+        let right = promote_to_f64(builder, right);
+        let condition = match operator {
+            BinaryOperator::Less => FloatCC::LessThan,
+            BinaryOperator::Greater => FloatCC::GreaterThan,
+            other => unreachable!("{} is not a comparison", other.symbol()),
+        };
+        builder.ins().fcmp(condition, left, right)
+    } else {
+        let condition = match operator {
+            BinaryOperator::Less => IntCC::SignedLessThan,
+            BinaryOperator::Greater => IntCC::SignedGreaterThan,
+            other => unreachable!("{} is not a comparison", other.symbol()),
+        };
+        builder.ins().icmp(condition, left.value, right.value)
+    }
+}
+
+/// After `break`/`continue` the current block is terminated, but statements
+/// may syntactically follow; they lower into a fresh block with no
+/// predecessors, which Cranelift accepts and later drops as dead code.
+fn start_unreachable_block(builder: &mut FunctionBuilder) {
+    let block = builder.create_block();
+    builder.switch_to_block(block);
+    builder.seal_block(block);
+}
+
+fn compile_expression(
+    builder: &mut FunctionBuilder,
+    context: &mut CodegenContext,
+    expression: &ExpressionNode,
+) -> Result<TypedValue, CodegenError> {
+    match expression {
+        ExpressionNode::NumberLiteral { value, .. } => Ok(TypedValue {
+            value: builder.ins().iconst(types::I64, *value),
+            ty: types::I64,
+        }),
+        ExpressionNode::FloatLiteral { value, .. } => Ok(TypedValue {
+            value: builder.ins().f64const(*value),
+            ty: types::F64,
+        }),
+        ExpressionNode::Variable { name, position } => match context.variables.get(name) {
+            Some((variable, ty)) => Ok(TypedValue {
+                ty: *ty,
+                value: builder.use_var(*variable),
+            }),
+            None => Err(CodegenError::at(
+                format!("Undefined variable: {}", name),
+                *position,
+            )),
+        },
+        ExpressionNode::Binary {
+            left,
+            operator,
+            right,
+            position,
+        } => {
+            let left = compile_expression(builder, context, left)?;
+            let right = compile_expression(builder, context, right)?;
+            // In expression position a comparison widens its flag to a 0/1
+            // `i64`; `compile_condition` branches on the flag directly.
+            if matches!(operator, BinaryOperator::Less | BinaryOperator::Greater) {
+                let flag = comparison_flag(builder, *operator, left, right);
+                return Ok(TypedValue {
+                    value: builder.ins().uextend(types::I64, flag),
+                    ty: types::I64,
+                });
+            }
+            // Mixed int/float operations promote the integer side to f64.
+            if left.ty == types::F64 || right.ty == types::F64 {
+                let left = promote_to_f64(builder, left);
+                let right = promote_to_f64(builder, right);
+                let value = match operator {
+                    BinaryOperator::Add => builder.ins().fadd(left, right),
+                    BinaryOperator::Subtract => builder.ins().fsub(left, right),
+                    BinaryOperator::Multiply => builder.ins().fmul(left, right),
+                    BinaryOperator::Divide => builder.ins().fdiv(left, right),
+                    unsupported => return Err(unsupported_operator(*unsupported, *position)),
+                };
+                return Ok(TypedValue {
+                    value,
+                    ty: types::F64,
+                });
+            }
+            let value = match operator {
+                BinaryOperator::Add => builder.ins().iadd(left.value, right.value),
+                BinaryOperator::Subtract => builder.ins().isub(left.value, right.value),
+                BinaryOperator::Multiply => builder.ins().imul(left.value, right.value),
+                BinaryOperator::Divide => builder.ins().sdiv(left.value, right.value),
+                unsupported => return Err(unsupported_operator(*unsupported, *position)),
+            };
+            Ok(TypedValue {
+                value,
+                ty: types::I64,
+            })
+        }
+        ExpressionNode::Call {
+            name,
+            arguments,
+            position,
+        } => compile_call(builder, context, name, arguments, *position),
+    }
+}
+
+/// The syntax crate can grow operators ahead of the backend; compiling one
+/// the backend can't lower yet fails with a pointed message instead of a
+/// panic or a build break.
+fn unsupported_operator(operator: BinaryOperator, position: Position) -> CodegenError {
+    CodegenError::at(
+        format!(
+            "operator {} not yet supported in the native backend",
+            operator.symbol()
+        ),
+        position,
+    )
+}
+
+fn compile_call(
+    builder: &mut FunctionBuilder,
+    context: &mut CodegenContext,
+    name: &str,
+    arguments: &[ExpressionNode],
+    position: Position,
+) -> Result<TypedValue, CodegenError> {
+    let mut values = Vec::with_capacity(arguments.len());
+    for argument in arguments {
+        values.push(compile_expression(builder, context, argument)?);
+    }
+
+    let callee = match context.runtime_functions.get(name) {
+        Some(callee) => callee,
+        None => {
+            return Err(CodegenError::at(
+                format!("Undefined function: {}", name),
+                position,
+            ))
+        }
+    };
+    if callee.parameters.len() != values.len() {
+        return Err(CodegenError::at(
+            format!(
+                "Function '{}' expects {} arguments, got {}",
+                name,
+                callee.parameters.len(),
+                values.len()
+            ),
+            position,
+        ));
+    }
+    let func_ref = callee.func_ref;
+    let parameters = callee.parameters.clone();
+    let returns = callee.returns;
+
+    // Arguments convert to the declared parameter types: ints promote to
+    // f64, floats truncate toward zero for i64 parameters.
+    let mut converted = Vec::with_capacity(values.len());
+    for (value, parameter) in values.into_iter().zip(parameters) {
+        let value = if value.ty == parameter {
+            value.value
+        } else if parameter == types::F64 {
+            builder.ins().fcvt_from_sint(types::F64, value.value)
+        } else {
+            builder.ins().fcvt_to_sint(types::I64, value.value)
+        };
+        converted.push(value);
+    }
+
+    let call = builder.ins().call(func_ref, &converted);
+    match returns {
+        Some(ty) => Ok(TypedValue {
+            value: builder.inst_results(call)[0],
+            ty,
+        }),
+        // A `void` runtime function evaluates to 0 in expression position.
+        None => Ok(TypedValue {
+            value: builder.ins().iconst(types::I64, 0),
+            ty: types::I64,
+        }),
+    }
+}
+
+fn promote_to_f64(builder: &mut FunctionBuilder, typed: TypedValue) -> Value {
+    if typed.ty == types::F64 {
+        typed.value
+    } else {
+        builder.ins().fcvt_from_sint(types::F64, typed.value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile_source(source: &str) -> Result<Vec<u8>, CodegenError> {
+        compile_with_runtime(source, &RuntimeInterface::empty())
+    }
+
+    fn compile_with_runtime(
+        source: &str,
+        runtime: &RuntimeInterface,
+    ) -> Result<Vec<u8>, CodegenError> {
+        let tokens = syntax::lexer::lex(source).expect("test sources should lex");
+        let program = syntax::parser::parse(&tokens).expect("test sources should parse");
+        compile_program_to_object(&program, "test", runtime, "main")
+    }
+
+    #[test]
+    fn compiles_arithmetic_to_an_object() {
+        let object_bytes = compile_source("let x = 2; x * 21;").unwrap();
+        assert!(!object_bytes.is_empty());
+    }
+
+    #[test]
+    fn compiles_float_arithmetic_to_an_object() {
+        let object_bytes = compile_source("let x = 1.5; x * 2.0 + 1;").unwrap();
+        assert!(!object_bytes.is_empty());
+    }
+
+    #[test]
+    fn the_entry_symbol_carries_the_requested_name() {
+        let tokens = syntax::lexer::lex("1 + 2;").unwrap();
+        let program = syntax::parser::parse(&tokens).unwrap();
+        let object_bytes =
+            compile_program_to_object(&program, "test", &RuntimeInterface::empty(), "amarok_entry")
+                .unwrap();
+        // The symbol table stores names as plain bytes, so the requested
+        // entry name must appear in the object verbatim.
+        let name = b"amarok_entry";
+        assert!(object_bytes
+            .windows(name.len())
+            .any(|window| window == name));
+    }
+
+    fn compile_ir(source: &str) -> String {
+        let tokens = syntax::lexer::lex(source).expect("test sources should lex");
+        let program = syntax::parser::parse(&tokens).expect("test sources should parse");
+        compile_program_to_ir(&program, "test", &RuntimeInterface::empty(), "main").unwrap()
+    }
+
+    #[test]
+    fn comparisons_compile_in_expression_position() {
+        // `1 < 2;` as the last expression makes `main` return its 0/1 value.
+        assert!(compile_source("1 < 2;").is_ok());
+        assert!(compile_source("1.5 > 0.5;").is_ok());
+        // In expression position the flag widens to an i64.
+        let ir = compile_ir("1 < 2;");
+        assert!(ir.contains("uextend"), "IR was:\n{}", ir);
+    }
+
+    #[test]
+    fn a_comparison_condition_branches_on_the_flag_directly() {
+        let ir = compile_ir("let i = 0; let n = 10; while (i < n) { let i = i + 1; }");
+        // Exactly the one icmp for `<`, feeding brif — no widening to 0/1
+        // and no second compare against zero.
+        assert_eq!(ir.matches("icmp").count(), 1, "IR was:\n{}", ir);
+        assert!(ir.contains("brif"), "IR was:\n{}", ir);
+        assert!(!ir.contains("uextend"), "IR was:\n{}", ir);
+    }
+
+    #[test]
+    fn a_float_condition_compares_on_float_flags() {
+        let ir = compile_ir("let x = 0.5; while (x > 1.5) { let x = x + 1.0; }");
+        assert!(ir.contains("fcmp"), "IR was:\n{}", ir);
+        assert!(!ir.contains("uextend"), "IR was:\n{}", ir);
+    }
+
+    #[test]
+    fn undefined_variable_is_an_error() {
+        let error = compile_source("missing + 1;").unwrap_err();
+        assert_eq!(error.message, "Undefined variable: missing");
+    }
+
+    #[test]
+    fn an_undefined_variable_error_carries_its_position() {
+        let error = compile_source("let x = 1;\nx + missing;").unwrap_err();
+        assert_eq!(error.message, "Undefined variable: missing");
+        assert_eq!(error.position, Some(Position::new(2, 5)));
+    }
+
+    #[test]
+    fn compiles_a_runtime_call() {
+        let runtime = RuntimeInterface::empty().with_function(
+            "amarok_answer",
+            vec![],
+            Some(RuntimeValueType::Int),
+        );
+        let object_bytes = compile_with_runtime("amarok_answer() + 1;", &runtime).unwrap();
+        assert!(!object_bytes.is_empty());
+    }
+
+    #[test]
+    fn calling_an_undeclared_function_is_an_error() {
+        let error = compile_source("missing();").unwrap_err();
+        assert_eq!(error.message, "Undefined function: missing");
+    }
+
+    #[test]
+    fn codegen_error_boxes_into_dyn_error() {
+        let error = compile_source("missing();").unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert_eq!(boxed.to_string(), "Undefined function: missing");
+    }
+}
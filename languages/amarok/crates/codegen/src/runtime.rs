@@ -0,0 +1,61 @@
+//! Descriptions of host functions compiled programs may call.
+//!
+//! # ABI
+//!
+//! Runtime functions use the platform's C calling convention (the default
+//! Cranelift call convention for the target). Parameters and return values
+//! are limited to `i64` ([`RuntimeValueType::Int`]) and `f64`
+//! ([`RuntimeValueType::Float`]), so a runtime implemented in C declares them
+//! as `long long` / `double`. A function with no return type is a C `void`
+//! function; calling one yields `0` in expression position.
+//!
+//! Codegen declares every function here with `Linkage::Import`; it is the
+//! driver's job to hand the linker an object or static library that defines
+//! the symbols.
+
+/// The value types that can cross the runtime boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuntimeValueType {
+    /// `i64`, a C `long long`.
+    Int,
+    /// `f64`, a C `double`.
+    Float,
+}
+
+/// One externally provided function.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RuntimeFunction {
+    /// The unmangled symbol name, exactly as the linker sees it.
+    pub name: String,
+    pub parameters: Vec<RuntimeValueType>,
+    /// `None` for `void` functions.
+    pub returns: Option<RuntimeValueType>,
+}
+
+/// The set of host functions a compiled program may call.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct RuntimeInterface {
+    pub functions: Vec<RuntimeFunction>,
+}
+
+impl RuntimeInterface {
+    /// An interface with no functions — fully self-contained programs.
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Add a function to the interface, builder style.
+    pub fn with_function(
+        mut self,
+        name: impl Into<String>,
+        parameters: Vec<RuntimeValueType>,
+        returns: Option<RuntimeValueType>,
+    ) -> Self {
+        self.functions.push(RuntimeFunction {
+            name: name.into(),
+            parameters,
+            returns,
+        });
+        self
+    }
+}
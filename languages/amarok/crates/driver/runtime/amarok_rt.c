@@ -0,0 +1,19 @@
+/* The tiny runtime linked into every compiled Amarok executable.
+ *
+ * ABI: the platform C calling convention, matching the codegen crate's
+ * runtime module — i64 parameters are `long long`, f64 are `double`, and
+ * strings are NUL-terminated `char` pointers. Each printer writes one line.
+ */
+#include <stdio.h>
+
+void amarok_print_int(long long value) {
+    printf("%lld\n", value);
+}
+
+void amarok_print_float(double value) {
+    printf("%g\n", value);
+}
+
+void amarok_print_str(const char *text) {
+    printf("%s\n", text);
+}
@@ -0,0 +1,372 @@
+//! Drives the native compilation pipeline: read a source file, parse it,
+//! generate an object file, and link it into an executable.
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::fs;
+
+use codegen::{RuntimeInterface, RuntimeValueType};
+
+/// The C source of the runtime bundled with the driver. It is compiled with
+/// the host `cc` and linked into every executable, so compiled programs are
+/// self-contained.
+const BUNDLED_RUNTIME_SOURCE: &str = include_str!("../runtime/amarok_rt.c");
+
+/// The functions the bundled runtime defines, ready to merge into
+/// [`CompilationOptions::runtime`] so programs can call them. The runtime
+/// also defines `amarok_print_str` for embedders; it is not declared here
+/// because [`RuntimeValueType`] has no string type yet.
+pub fn bundled_runtime_interface() -> RuntimeInterface {
+    RuntimeInterface::empty()
+        .with_function("amarok_print_int", vec![RuntimeValueType::Int], None)
+        .with_function("amarok_print_float", vec![RuntimeValueType::Float], None)
+}
+
+/// Knobs for a compilation. More will grow here over time.
+#[derive(Debug, Clone, Default)]
+pub struct CompilationOptions {
+    pub output_path: Option<PathBuf>,
+    /// Host functions the program may call; see [`codegen::runtime`] for the
+    /// ABI. Every symbol must be defined by one of `runtime_objects`.
+    pub runtime: RuntimeInterface,
+    /// Extra objects or static libraries handed to the linker.
+    pub runtime_objects: Vec<PathBuf>,
+    /// The exported name of the entry function; `main` when unset. Embedders
+    /// pick a distinct name so the object links into a program that already
+    /// has a `main`.
+    pub entry_name: Option<String>,
+}
+
+impl CompilationOptions {
+    /// Options with every knob left at its default.
+    pub fn simple() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug)]
+pub enum DriverError {
+    Io(io::Error),
+    Syntax(syntax::SyntaxError),
+    Codegen(codegen::CodegenError),
+    Link(String),
+}
+
+impl fmt::Display for DriverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "i/o error: {}", error),
+            Self::Syntax(error) => write!(f, "syntax error: {}", error),
+            Self::Codegen(error) => write!(f, "codegen error: {}", error),
+            Self::Link(message) => write!(f, "link error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for DriverError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            Self::Syntax(error) => Some(error),
+            Self::Codegen(error) => Some(error),
+            Self::Link(_) => None,
+        }
+    }
+}
+
+/// Render `error` for humans, with a caret pointing into `source` when the
+/// error knows where it happened — the same shape the interpreter CLI uses.
+pub fn render_error(file: &str, source: &str, error: &DriverError) -> String {
+    let (message, position) = match error {
+        DriverError::Syntax(error) => (
+            error.message.as_str(),
+            Some((error.line_number, error.column_number)),
+        ),
+        DriverError::Codegen(error) => (
+            error.message.as_str(),
+            error
+                .position
+                .map(|position| (position.line_number, position.column_number)),
+        ),
+        other => return format!("error: {}\n", other),
+    };
+    let mut rendered = format!("error: {}\n", message);
+    let Some((line_number, column_number)) = position else {
+        rendered.push_str(&format!(" --> {}\n", file));
+        return rendered;
+    };
+    let line_text = source.lines().nth(line_number - 1).unwrap_or("");
+    // Clamp to the displayed line so a stale position never pushes the caret
+    // past the text it points into.
+    let caret_indent = (column_number - 1).min(line_text.chars().count());
+    rendered.push_str(&format!(" --> {}:{}:{}\n", file, line_number, column_number));
+    rendered.push_str(&format!("{}\n", line_text));
+    rendered.push_str(&format!("{}^\n", " ".repeat(caret_indent)));
+    rendered
+}
+
+/// Compile in-memory source to object bytes, without touching the filesystem
+/// or the linker. Embedders can feed the bytes to their own linker; the
+/// executable path is [`compile_file`].
+pub fn compile_source(
+    source: &str,
+    module_name: &str,
+    options: &CompilationOptions,
+) -> Result<Vec<u8>, DriverError> {
+    let tokens = syntax::lexer::lex(source).map_err(DriverError::Syntax)?;
+    let program = syntax::parser::parse(&tokens).map_err(DriverError::Syntax)?;
+    let entry_name = options.entry_name.as_deref().unwrap_or("main");
+    codegen::compile_program_to_object(&program, module_name, &options.runtime, entry_name)
+        .map_err(DriverError::Codegen)
+}
+
+/// Compile `path` to a native executable, returning the executable's path.
+pub fn compile_file(path: &Path, options: &CompilationOptions) -> Result<PathBuf, DriverError> {
+    let source = fs::read_to_string(path).map_err(DriverError::Io)?;
+    let module_name = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("program");
+    let object_bytes = compile_source(&source, module_name, options)?;
+
+    let output_path = options
+        .output_path
+        .clone()
+        .unwrap_or_else(|| path.with_extension(""));
+    let object_path = output_path.with_extension("o");
+    fs::write(&object_path, &object_bytes).map_err(DriverError::Io)?;
+
+    // The bundled runtime rides along on every link, so a program that only
+    // prints needs no extra runtime_objects.
+    let build_directory = output_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let bundled_runtime = compile_bundled_runtime(build_directory)?;
+    let mut link_objects = vec![bundled_runtime];
+    link_objects.extend(options.runtime_objects.iter().cloned());
+
+    link(&object_path, &link_objects, &output_path)?;
+    Ok(output_path)
+}
+
+/// Write the bundled runtime's C source into `directory` and compile it to
+/// an object there, returning the object's path.
+fn compile_bundled_runtime(directory: &Path) -> Result<PathBuf, DriverError> {
+    let source_path = directory.join("amarok_rt.c");
+    let object_path = directory.join("amarok_rt.o");
+    fs::write(&source_path, BUNDLED_RUNTIME_SOURCE).map_err(DriverError::Io)?;
+    let status = Command::new("cc")
+        .arg("-c")
+        .arg(&source_path)
+        .arg("-o")
+        .arg(&object_path)
+        .status()
+        .map_err(DriverError::Io)?;
+    if status.success() {
+        Ok(object_path)
+    } else {
+        Err(DriverError::Link(format!(
+            "runtime compilation exited with {}",
+            status
+        )))
+    }
+}
+
+fn link(
+    object_path: &Path,
+    runtime_objects: &[PathBuf],
+    output_path: &Path,
+) -> Result<(), DriverError> {
+    let status = Command::new("cc")
+        .arg(object_path)
+        .args(runtime_objects)
+        .arg("-o")
+        .arg(output_path)
+        .status()
+        .map_err(DriverError::Io)?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(DriverError::Link(format!("linker exited with {}", status)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn scratch_directory() -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("amarok-driver-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Compile `source` into a scratch directory and return the executable's
+    /// exit code.
+    fn compile_and_run_with(name: &str, source: &str, options: &CompilationOptions) -> i32 {
+        let source_path = scratch_directory().join(format!("{}.amk", name));
+        fs::write(&source_path, source).unwrap();
+
+        let executable = compile_file(&source_path, options).unwrap();
+        let status = Command::new(&executable).status().unwrap();
+        status.code().expect("the program should exit normally")
+    }
+
+    fn compile_and_run(name: &str, source: &str) -> i32 {
+        compile_and_run_with(name, source, &CompilationOptions::simple())
+    }
+
+    #[test]
+    fn compiles_and_runs_integer_arithmetic() {
+        assert_eq!(compile_and_run("ints", "let x = 6; x * 7;"), 42);
+    }
+
+    #[test]
+    fn compiles_and_runs_float_arithmetic() {
+        assert_eq!(compile_and_run("floats", "1.5 * 2.0;"), 3);
+    }
+
+    #[test]
+    fn compiles_and_runs_a_counting_loop() {
+        let source = "let i = 0; let total = 0; \
+                      while (10 - i) { let total = total + i; let i = i + 1; } \
+                      total;";
+        assert_eq!(compile_and_run("loop", source), 45);
+    }
+
+    #[test]
+    fn break_leaves_a_loop_early() {
+        let source = "let i = 0; while (1) { let i = i + 1; break; } i;";
+        assert_eq!(compile_and_run("break_early", source), 1);
+    }
+
+    #[test]
+    fn continue_skips_the_rest_of_the_body() {
+        let source = "let i = 3; let total = 0; \
+                      while (i) { let i = i - 1; continue; let total = total + 100; } \
+                      total;";
+        assert_eq!(compile_and_run("continue_skips", source), 0);
+    }
+
+    #[test]
+    fn break_targets_the_innermost_loop() {
+        let source = "let i = 3; let total = 0; \
+                      while (i) { let i = i - 1; while (1) { let total = total + 1; break; } } \
+                      total;";
+        assert_eq!(compile_and_run("nested_break", source), 3);
+    }
+
+    #[test]
+    fn a_custom_entry_name_links_into_a_host_main() {
+        let directory = scratch_directory();
+        let options = CompilationOptions {
+            entry_name: Some("amarok_entry".to_string()),
+            ..CompilationOptions::simple()
+        };
+        let object_bytes = compile_source("40 + 2;", "embedded", &options).unwrap();
+        let object_path = directory.join("embedded.o");
+        fs::write(&object_path, &object_bytes).unwrap();
+
+        let host_source = directory.join("host_main.c");
+        fs::write(
+            &host_source,
+            "long long amarok_entry(void);\nint main(void) { return (int)amarok_entry(); }\n",
+        )
+        .unwrap();
+        let executable = directory.join("embedded");
+        let status = Command::new("cc")
+            .arg(&host_source)
+            .arg(&object_path)
+            .arg("-o")
+            .arg(&executable)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let status = Command::new(&executable).status().unwrap();
+        assert_eq!(status.code(), Some(42));
+    }
+
+    #[test]
+    fn loop_control_outside_a_loop_is_a_codegen_error() {
+        let error = compile_source("break;", "stray", &CompilationOptions::simple()).unwrap_err();
+        assert!(matches!(error, DriverError::Codegen(_)));
+    }
+
+    #[test]
+    fn an_undeclared_variable_renders_with_a_caret() {
+        let source = "let x = 1;\nx + missing;";
+        let error = compile_source(source, "caret", &CompilationOptions::simple()).unwrap_err();
+        assert_eq!(
+            render_error("caret.amk", source, &error),
+            "error: Undefined variable: missing\n \
+             --> caret.amk:2:5\n\
+             x + missing;\n    \
+             ^\n"
+        );
+    }
+
+    #[test]
+    fn driver_error_boxes_into_dyn_error_with_a_source() {
+        let error = compile_source("let = ;", "bad", &CompilationOptions::simple()).unwrap_err();
+        let boxed: Box<dyn std::error::Error> = Box::new(error);
+        assert!(boxed.source().is_some());
+    }
+
+    #[test]
+    fn compiles_source_from_memory_to_object_bytes() {
+        let bytes =
+            compile_source("1 + 2;", "in_memory", &CompilationOptions::simple()).unwrap();
+        assert!(!bytes.is_empty());
+    }
+
+    #[test]
+    fn the_bundled_runtime_prints_from_a_compiled_program() {
+        let source_path = scratch_directory().join("hello_print.amk");
+        fs::write(&source_path, "amarok_print_int(42);").unwrap();
+        let options = CompilationOptions {
+            runtime: bundled_runtime_interface(),
+            ..CompilationOptions::simple()
+        };
+        let executable = compile_file(&source_path, &options).unwrap();
+        let output = Command::new(&executable).output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+        assert_eq!(output.status.code(), Some(0));
+    }
+
+    #[test]
+    fn links_against_a_runtime_object() {
+        use codegen::RuntimeValueType;
+
+        let directory = scratch_directory();
+        let shim_source = directory.join("answer_shim.c");
+        let shim_object = directory.join("answer_shim.o");
+        fs::write(&shim_source, "long long amarok_answer(void) { return 40; }\n").unwrap();
+        let status = Command::new("cc")
+            .arg("-c")
+            .arg(&shim_source)
+            .arg("-o")
+            .arg(&shim_object)
+            .status()
+            .unwrap();
+        assert!(status.success());
+
+        let options = CompilationOptions {
+            runtime: RuntimeInterface::empty().with_function(
+                "amarok_answer",
+                vec![],
+                Some(RuntimeValueType::Int),
+            ),
+            runtime_objects: vec![shim_object],
+            ..CompilationOptions::simple()
+        };
+        assert_eq!(
+            compile_and_run_with("external", "amarok_answer() + 2;", &options),
+            42
+        );
+    }
+}
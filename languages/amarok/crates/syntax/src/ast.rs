@@ -0,0 +1,189 @@
+//! The AST consumed by the codegen backend.
+
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// A 1-based line/column position, carried by AST nodes so errors raised
+/// long after parsing can still point at the source that caused them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Position {
+    pub line_number: usize,
+    pub column_number: usize,
+}
+
+impl Position {
+    pub fn new(line_number: usize, column_number: usize) -> Self {
+        Self {
+            line_number,
+            column_number,
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line_number, self.column_number)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub struct ProgramNode {
+    pub statements: Vec<StatementNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Hash)]
+pub enum StatementNode {
+    /// `let name = value;`
+    Let {
+        name: String,
+        value: ExpressionNode,
+        position: Position,
+    },
+    /// A bare expression; the last one in the program becomes `main`'s
+    /// return value.
+    Expression {
+        expression: ExpressionNode,
+        position: Position,
+    },
+    /// `while (condition) { body }` — a true comparison or any non-zero
+    /// integer keeps the loop running.
+    While {
+        condition: ExpressionNode,
+        body: Vec<StatementNode>,
+        position: Position,
+    },
+    /// `break;` — jump past the innermost enclosing loop.
+    Break { position: Position },
+    /// `continue;` — jump back to the innermost enclosing loop's condition.
+    Continue { position: Position },
+}
+
+impl StatementNode {
+    /// The statement's first token — `let`, `while`, the keyword itself for
+    /// loop control, or the opening token of a bare expression.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::Let { position, .. }
+            | Self::Expression { position, .. }
+            | Self::While { position, .. }
+            | Self::Break { position }
+            | Self::Continue { position } => *position,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionNode {
+    NumberLiteral {
+        value: i64,
+        position: Position,
+    },
+    FloatLiteral {
+        value: f64,
+        position: Position,
+    },
+    Variable {
+        name: String,
+        position: Position,
+    },
+    Binary {
+        left: Box<ExpressionNode>,
+        operator: BinaryOperator,
+        right: Box<ExpressionNode>,
+        /// The operator token's position, the natural place to point an
+        /// error about the operation itself.
+        position: Position,
+    },
+    /// `name(arguments)` — resolved against the runtime interface at codegen
+    /// time; there are no user-defined functions in the native pipeline yet.
+    Call {
+        name: String,
+        arguments: Vec<ExpressionNode>,
+        position: Position,
+    },
+}
+
+/// Hashing is structural, matching equality field for field, except that a
+/// float hashes its bit pattern (`f64` itself is not `Hash`). `0.0` and
+/// `-0.0` therefore compare equal but hash differently — acceptable for
+/// cache keys, where the rare extra miss is harmless.
+impl Hash for ExpressionNode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Self::NumberLiteral { value, position } => {
+                value.hash(state);
+                position.hash(state);
+            }
+            Self::FloatLiteral { value, position } => {
+                value.to_bits().hash(state);
+                position.hash(state);
+            }
+            Self::Variable { name, position } => {
+                name.hash(state);
+                position.hash(state);
+            }
+            Self::Binary {
+                left,
+                operator,
+                right,
+                position,
+            } => {
+                left.hash(state);
+                operator.hash(state);
+                right.hash(state);
+                position.hash(state);
+            }
+            Self::Call {
+                name,
+                arguments,
+                position,
+            } => {
+                name.hash(state);
+                arguments.hash(state);
+                position.hash(state);
+            }
+        }
+    }
+}
+
+impl ExpressionNode {
+    /// Where to point a diagnostic about this expression: the operator for a
+    /// binary expression, the expression's first token otherwise.
+    pub fn position(&self) -> Position {
+        match self {
+            Self::NumberLiteral { position, .. }
+            | Self::FloatLiteral { position, .. }
+            | Self::Variable { position, .. }
+            | Self::Binary { position, .. }
+            | Self::Call { position, .. } => *position,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    /// `<` — yields 1 or 0 in expression position; in a condition it lowers
+    /// to a branch on the comparison itself.
+    Less,
+    /// `>` — see [`BinaryOperator::Less`].
+    Greater,
+}
+
+impl BinaryOperator {
+    /// The operator as written in source, for diagnostics.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Less => "<",
+            Self::Greater => ">",
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use crate::token::{Token, TokenKind};
+use crate::SyntaxError;
+
+/// Split `source` into tokens, tracking line and column for diagnostics.
+pub fn lex(source: &str) -> Result<Vec<Token>, SyntaxError> {
+    let characters: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut index = 0;
+    let mut line_number = 1;
+    let mut column_number = 1;
+
+    while index < characters.len() {
+        let character = characters[index];
+        match character {
+            ' ' | '\t' | '\r' => {
+                index += 1;
+                column_number += 1;
+            }
+            '\n' => {
+                index += 1;
+                line_number += 1;
+                column_number = 1;
+            }
+            '#' => {
+                // Comments run to the end of the line.
+                while index < characters.len() && characters[index] != '\n' {
+                    index += 1;
+                }
+            }
+            '0'..='9' => {
+                let (text, consumed, is_float) = lex_number(&characters[index..]);
+                let parsed = if is_float {
+                    text.parse().map(TokenKind::Float).ok()
+                } else {
+                    text.parse().map(TokenKind::Number).ok()
+                };
+                let kind = parsed.ok_or_else(|| {
+                    SyntaxError::new(
+                        format!("invalid number: {}", text),
+                        line_number,
+                        column_number,
+                    )
+                })?;
+                tokens.push(Token::new(kind, line_number, column_number));
+                index += consumed;
+                column_number += consumed;
+            }
+            'a'..='z' | 'A'..='Z' | '_' => {
+                let start = index;
+                while index < characters.len()
+                    && (characters[index].is_ascii_alphanumeric() || characters[index] == '_')
+                {
+                    index += 1;
+                }
+                let text: String = characters[start..index].iter().collect();
+                let kind = match text.as_str() {
+                    "let" => TokenKind::Let,
+                    "while" => TokenKind::While,
+                    "break" => TokenKind::Break,
+                    "continue" => TokenKind::Continue,
+                    _ => TokenKind::Identifier(text),
+                };
+                tokens.push(Token::new(kind, line_number, column_number));
+                column_number += index - start;
+            }
+            _ => {
+                let kind = match character {
+                    '+' => TokenKind::Plus,
+                    '-' => TokenKind::Minus,
+                    '*' => TokenKind::Star,
+                    '/' => TokenKind::Slash,
+                    '=' => TokenKind::Equals,
+                    '<' => TokenKind::Less,
+                    '>' => TokenKind::Greater,
+                    ';' => TokenKind::Semicolon,
+                    ',' => TokenKind::Comma,
+                    '(' => TokenKind::LeftParen,
+                    ')' => TokenKind::RightParen,
+                    '{' => TokenKind::LeftBrace,
+                    '}' => TokenKind::RightBrace,
+                    other => {
+                        return Err(SyntaxError::new(
+                            format!("unexpected character: {}", other),
+                            line_number,
+                            column_number,
+                        ))
+                    }
+                };
+                tokens.push(Token::new(kind, line_number, column_number));
+                index += 1;
+                column_number += 1;
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Scan a number, returning its text, how many characters were consumed, and
+/// whether it has a fractional part. A `.` only belongs to the number when a
+/// digit follows it, so `1.x` lexes as `1` `.` `x`.
+fn lex_number(characters: &[char]) -> (String, usize, bool) {
+    let mut consumed = 0;
+    while consumed < characters.len() && characters[consumed].is_ascii_digit() {
+        consumed += 1;
+    }
+    let mut is_float = false;
+    if consumed + 1 < characters.len()
+        && characters[consumed] == '.'
+        && characters[consumed + 1].is_ascii_digit()
+    {
+        is_float = true;
+        consumed += 1;
+        while consumed < characters.len() && characters[consumed].is_ascii_digit() {
+            consumed += 1;
+        }
+    }
+    (characters[..consumed].iter().collect(), consumed, is_float)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lex_let_binding() {
+        let tokens = lex("let x = 5;").unwrap();
+        let kinds: Vec<_> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Let,
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::Equals,
+                TokenKind::Number(5),
+                TokenKind::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_float() {
+        let tokens = lex("1.5 * 2.0;").unwrap();
+        let kinds: Vec<_> = tokens.into_iter().map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                TokenKind::Float(1.5),
+                TokenKind::Star,
+                TokenKind::Float(2.0),
+                TokenKind::Semicolon,
+            ]
+        );
+    }
+
+    #[test]
+    fn dot_without_a_following_digit_is_not_part_of_a_number() {
+        let error = lex("1.x;").unwrap_err();
+        assert!(error.message.contains("unexpected character: ."));
+    }
+
+    #[test]
+    fn tracks_line_and_column() {
+        let tokens = lex("1;\n  2;").unwrap();
+        assert_eq!((tokens[0].line_number, tokens[0].column_number), (1, 1));
+        assert_eq!((tokens[2].line_number, tokens[2].column_number), (2, 3));
+    }
+
+    #[test]
+    fn rejects_unknown_characters() {
+        let error = lex("1 @ 2").unwrap_err();
+        assert_eq!(error.column_number, 3);
+    }
+}
@@ -0,0 +1,42 @@
+//! Frontend for the native Amarok compiler: lexer, token stream, parser, and
+//! the AST handed to codegen.
+//!
+//! This is a deliberately small subset of the language the interpreter
+//! understands: `let` bindings and integer arithmetic.
+
+pub mod ast;
+pub mod lexer;
+pub mod parser;
+pub mod token;
+
+use std::fmt;
+
+/// A lexing or parsing failure, located by line and column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxError {
+    pub message: String,
+    pub line_number: usize,
+    pub column_number: usize,
+}
+
+impl SyntaxError {
+    pub fn new(message: impl Into<String>, line_number: usize, column_number: usize) -> Self {
+        Self {
+            message: message.into(),
+            line_number,
+            column_number,
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.line_number, self.column_number, self.message
+        )
+    }
+}
+
+impl std::error::Error for SyntaxError {}
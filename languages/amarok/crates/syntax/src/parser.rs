@@ -0,0 +1,338 @@
+use crate::ast::{BinaryOperator, ExpressionNode, Position, ProgramNode, StatementNode};
+use crate::token::{Token, TokenKind};
+use crate::SyntaxError;
+
+/// Parse a token stream into a program.
+pub fn parse(tokens: &[Token]) -> Result<ProgramNode, SyntaxError> {
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+    };
+    let mut statements = Vec::new();
+    while parser.position < parser.tokens.len() {
+        statements.push(parser.parse_statement()?);
+    }
+    Ok(ProgramNode { statements })
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn parse_statement(&mut self) -> Result<StatementNode, SyntaxError> {
+        // Every statement records its first token, so errors raised during
+        // codegen can point back at the source.
+        let position = self.current_position();
+
+        if self.check(&TokenKind::Let) {
+            self.advance();
+            let name = self.expect_identifier()?;
+            self.expect(TokenKind::Equals)?;
+            let value = self.parse_expression()?;
+            self.expect(TokenKind::Semicolon)?;
+            return Ok(StatementNode::Let {
+                name,
+                value,
+                position,
+            });
+        }
+
+        if self.check(&TokenKind::While) {
+            self.advance();
+            self.expect(TokenKind::LeftParen)?;
+            let condition = self.parse_expression()?;
+            self.expect(TokenKind::RightParen)?;
+            let body = self.parse_block()?;
+            return Ok(StatementNode::While {
+                condition,
+                body,
+                position,
+            });
+        }
+
+        if self.check(&TokenKind::Break) {
+            self.advance();
+            self.expect(TokenKind::Semicolon)?;
+            return Ok(StatementNode::Break { position });
+        }
+
+        if self.check(&TokenKind::Continue) {
+            self.advance();
+            self.expect(TokenKind::Semicolon)?;
+            return Ok(StatementNode::Continue { position });
+        }
+
+        let expression = self.parse_expression()?;
+        self.expect(TokenKind::Semicolon)?;
+        Ok(StatementNode::Expression {
+            expression,
+            position,
+        })
+    }
+
+    fn parse_block(&mut self) -> Result<Vec<StatementNode>, SyntaxError> {
+        self.expect(TokenKind::LeftBrace)?;
+        let mut statements = Vec::new();
+        while !self.check(&TokenKind::RightBrace) {
+            if self.position >= self.tokens.len() {
+                return Err(self.error_here("unexpected end of input, expected `}`"));
+            }
+            statements.push(self.parse_statement()?);
+        }
+        self.expect(TokenKind::RightBrace)?;
+        Ok(statements)
+    }
+
+    fn parse_expression(&mut self) -> Result<ExpressionNode, SyntaxError> {
+        let mut left = self.parse_additive()?;
+        loop {
+            let operator = match self.peek_kind() {
+                Some(TokenKind::Less) => BinaryOperator::Less,
+                Some(TokenKind::Greater) => BinaryOperator::Greater,
+                _ => break,
+            };
+            let position = self.current_position();
+            self.advance();
+            let right = self.parse_additive()?;
+            left = ExpressionNode::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                position,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_additive(&mut self) -> Result<ExpressionNode, SyntaxError> {
+        let mut left = self.parse_factor()?;
+        loop {
+            let operator = match self.peek_kind() {
+                Some(TokenKind::Plus) => BinaryOperator::Add,
+                Some(TokenKind::Minus) => BinaryOperator::Subtract,
+                _ => break,
+            };
+            let position = self.current_position();
+            self.advance();
+            let right = self.parse_factor()?;
+            left = ExpressionNode::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                position,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_factor(&mut self) -> Result<ExpressionNode, SyntaxError> {
+        let mut left = self.parse_primary()?;
+        loop {
+            let operator = match self.peek_kind() {
+                Some(TokenKind::Star) => BinaryOperator::Multiply,
+                Some(TokenKind::Slash) => BinaryOperator::Divide,
+                _ => break,
+            };
+            let position = self.current_position();
+            self.advance();
+            let right = self.parse_primary()?;
+            left = ExpressionNode::Binary {
+                left: Box::new(left),
+                operator,
+                right: Box::new(right),
+                position,
+            };
+        }
+        Ok(left)
+    }
+
+    fn parse_primary(&mut self) -> Result<ExpressionNode, SyntaxError> {
+        let position = self.current_position();
+        match self.peek_kind().cloned() {
+            Some(TokenKind::Number(value)) => {
+                self.advance();
+                Ok(ExpressionNode::NumberLiteral { value, position })
+            }
+            Some(TokenKind::Float(value)) => {
+                self.advance();
+                Ok(ExpressionNode::FloatLiteral { value, position })
+            }
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                if self.check(&TokenKind::LeftParen) {
+                    self.advance();
+                    let mut arguments = Vec::new();
+                    if !self.check(&TokenKind::RightParen) {
+                        loop {
+                            arguments.push(self.parse_expression()?);
+                            if !self.check(&TokenKind::Comma) {
+                                break;
+                            }
+                            self.advance();
+                        }
+                    }
+                    self.expect(TokenKind::RightParen)?;
+                    return Ok(ExpressionNode::Call {
+                        name,
+                        arguments,
+                        position,
+                    });
+                }
+                Ok(ExpressionNode::Variable { name, position })
+            }
+            Some(TokenKind::LeftParen) => {
+                self.advance();
+                let expression = self.parse_expression()?;
+                self.expect(TokenKind::RightParen)?;
+                Ok(expression)
+            }
+            Some(other) => Err(self.error_here(format!("expected an expression, found {:?}", other))),
+            None => Err(self.error_here("unexpected end of input")),
+        }
+    }
+
+    fn peek_kind(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.position).map(|token| &token.kind)
+    }
+
+    /// The current token's position, or the end of the last token when the
+    /// input has run out.
+    fn current_position(&self) -> Position {
+        self.tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map(|token| Position::new(token.line_number, token.column_number))
+            .unwrap_or_else(|| Position::new(1, 1))
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        self.peek_kind() == Some(kind)
+    }
+
+    fn advance(&mut self) {
+        self.position += 1;
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<(), SyntaxError> {
+        if self.check(&kind) {
+            self.advance();
+            Ok(())
+        } else {
+            Err(self.error_here(format!("expected {:?}", kind)))
+        }
+    }
+
+    fn expect_identifier(&mut self) -> Result<String, SyntaxError> {
+        match self.peek_kind().cloned() {
+            Some(TokenKind::Identifier(name)) => {
+                self.advance();
+                Ok(name)
+            }
+            _ => Err(self.error_here("expected an identifier")),
+        }
+    }
+
+    fn error_here(&self, message: impl Into<String>) -> SyntaxError {
+        let (line_number, column_number) = self
+            .tokens
+            .get(self.position)
+            .or_else(|| self.tokens.last())
+            .map(|token| (token.line_number, token.column_number))
+            .unwrap_or((1, 1));
+        SyntaxError::new(message, line_number, column_number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lexer::lex;
+
+    fn parse_source(source: &str) -> Result<ProgramNode, SyntaxError> {
+        parse(&lex(source)?)
+    }
+
+    #[test]
+    fn parse_let_and_expression() {
+        let program = parse_source("let x = 2; x * 3;").unwrap();
+        assert_eq!(program.statements.len(), 2);
+        assert_eq!(
+            program.statements[0],
+            StatementNode::Let {
+                name: "x".to_string(),
+                value: ExpressionNode::NumberLiteral {
+                    value: 2,
+                    position: Position::new(1, 9),
+                },
+                position: Position::new(1, 1),
+            }
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        let program = parse_source("1 + 2 * 3;").unwrap();
+        match &program.statements[0] {
+            StatementNode::Expression {
+                expression:
+                    ExpressionNode::Binary {
+                        operator: BinaryOperator::Add,
+                        right,
+                        ..
+                    },
+                ..
+            } => assert!(matches!(
+                **right,
+                ExpressionNode::Binary {
+                    operator: BinaryOperator::Multiply,
+                    ..
+                }
+            )),
+            other => panic!("expected addition at the top, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn missing_semicolon_is_an_error() {
+        let error = parse_source("let x = 1").unwrap_err();
+        assert!(error.message.contains("Semicolon"));
+    }
+
+    #[test]
+    fn parse_while_with_loop_control() {
+        let program = parse_source("while (x) { break; continue; }").unwrap();
+        assert_eq!(
+            program.statements[0],
+            StatementNode::While {
+                condition: ExpressionNode::Variable {
+                    name: "x".to_string(),
+                    position: Position::new(1, 8),
+                },
+                body: vec![
+                    StatementNode::Break {
+                        position: Position::new(1, 13)
+                    },
+                    StatementNode::Continue {
+                        position: Position::new(1, 20)
+                    },
+                ],
+                position: Position::new(1, 1),
+            }
+        );
+    }
+
+    #[test]
+    fn a_statement_records_its_leading_token_position() {
+        let program = parse_source("let x = 1;\n  x + 1;").unwrap();
+        assert_eq!(program.statements[0].position(), Position::new(1, 1));
+        assert_eq!(program.statements[1].position(), Position::new(2, 3));
+    }
+
+    #[test]
+    fn an_unclosed_loop_body_is_an_error() {
+        let error = parse_source("while (x) { break;").unwrap_err();
+        assert!(error.message.contains("unexpected end of input"));
+    }
+}
@@ -0,0 +1,40 @@
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(i64),
+    Float(f64),
+    Identifier(String),
+    Let,
+    While,
+    Break,
+    Continue,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Equals,
+    Less,
+    Greater,
+    Semicolon,
+    Comma,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub line_number: usize,
+    pub column_number: usize,
+}
+
+impl Token {
+    pub fn new(kind: TokenKind, line_number: usize, column_number: usize) -> Self {
+        Self {
+            kind,
+            line_number,
+            column_number,
+        }
+    }
+}